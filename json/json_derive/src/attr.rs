@@ -0,0 +1,321 @@
+use syn::{
+    ext::IdentExt, spanned::Spanned, Attribute, Data, DeriveInput, Error, ExprPath, Fields, Ident,
+    LitStr, Member,
+};
+
+/// How `rename_all` turns a field or variant name into a key.
+#[derive(Clone, Copy)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(lit: &LitStr) -> Result<Self, Error> {
+        Ok(match lit.value().as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            _ => {
+                return Err(Error::new(
+                    lit.span(),
+                    "unknown rename rule, expected one of \"lowercase\", \"UPPERCASE\", \
+                     \"PascalCase\", \"camelCase\", \"snake_case\", \"SCREAMING_SNAKE_CASE\", \
+                     \"kebab-case\" or \"SCREAMING-KEBAB-CASE\"",
+                ))
+            }
+        })
+    }
+
+    pub fn apply(&self, name: &str) -> String {
+        let words = words(name);
+
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        };
+
+        match self {
+            Self::Lower => name.to_lowercase(),
+            Self::Upper => name.to_uppercase(),
+            Self::Pascal => words.iter().map(capitalize).collect(),
+            Self::Camel => {
+                let mut words = words.iter();
+
+                let first = words.next().cloned().unwrap_or_default();
+
+                first + &words.map(capitalize).collect::<String>()
+            }
+            Self::Snake => words.join("_"),
+            Self::ScreamingSnake => words.join("_").to_uppercase(),
+            Self::Kebab => words.join("-"),
+            Self::ScreamingKebab => words.join("-").to_uppercase(),
+        }
+    }
+}
+
+/// Splits a `snake_case` or `PascalCase` name into lowercase words.
+fn words(name: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+
+    for c in name.chars() {
+        if c == '_' || (c.is_uppercase() && !word.is_empty()) {
+            if !word.is_empty() {
+                words.push(word);
+            }
+
+            word = String::new();
+
+            if c == '_' {
+                continue;
+            }
+        }
+
+        word.extend(c.to_lowercase());
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// How the variant of an enum is represented.
+pub enum Tagging {
+    /// `"Unit"` or `{ "Variant": content }`
+    External,
+    /// `{ "tag": "Variant", ...fields }`
+    Internal { tag: String },
+    /// `{ "tag": "Variant", "content": content }`
+    Adjacent { tag: String, content: String },
+    /// Only the content
+    Untagged,
+}
+
+pub enum DefaultValue {
+    /// `Default::default()`
+    Trait,
+    /// A function returning the value
+    Path(ExprPath),
+}
+
+pub struct Field {
+    pub member: Member,
+    /// The name the field is bound to when matching on a variant
+    pub binding: Ident,
+    pub key: String,
+    pub default: Option<DefaultValue>,
+    pub flatten: bool,
+}
+
+pub enum Shape {
+    Unit,
+    Newtype(Field),
+    Tuple(Vec<Field>),
+    Named(Vec<Field>),
+}
+
+pub struct Variant {
+    pub ident: Ident,
+    pub name: String,
+    pub shape: Shape,
+}
+
+pub enum Body {
+    Struct(Shape),
+    Enum(Tagging, Vec<Variant>),
+}
+
+/// Reads the `#[json(...)]` attributes of the type, its fields and variants.
+pub fn parse(input: &DeriveInput) -> Result<Body, Error> {
+    let mut rename_all = None;
+    let mut tag = None;
+    let mut content = None;
+    let mut untagged = false;
+
+    for attr in json_attrs(&input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                rename_all = Some(RenameRule::parse(&meta.value()?.parse()?)?);
+            } else if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("content") {
+                content = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("untagged") {
+                untagged = true;
+            } else {
+                return Err(meta.error("unknown json attribute"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    match &input.data {
+        Data::Struct(data) => {
+            if tag.is_some() || content.is_some() || untagged {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "`tag`, `content` and `untagged` can only be used on enums",
+                ));
+            }
+
+            Ok(Body::Struct(shape(&data.fields, rename_all)?))
+        }
+        Data::Enum(data) => {
+            let tagging = match (tag, content, untagged) {
+                (None, None, false) => Tagging::External,
+                (Some(tag), None, false) => Tagging::Internal { tag },
+                (Some(tag), Some(content), false) => Tagging::Adjacent { tag, content },
+                (None, None, true) => Tagging::Untagged,
+                (None, Some(_), false) => {
+                    return Err(Error::new(
+                        input.ident.span(),
+                        "`content` needs a `tag` to go with it",
+                    ))
+                }
+                _ => {
+                    return Err(Error::new(
+                        input.ident.span(),
+                        "`untagged` can't be used with `tag` or `content`",
+                    ))
+                }
+            };
+
+            let mut variants = vec![];
+
+            for variant in &data.variants {
+                let mut name = None;
+
+                for attr in json_attrs(&variant.attrs) {
+                    attr.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("rename") {
+                            name = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else {
+                            return Err(meta.error("unknown json attribute"));
+                        }
+
+                        Ok(())
+                    })?;
+                }
+
+                let ident = variant.ident.unraw().to_string();
+
+                let shape = shape(&variant.fields, None)?;
+
+                // the content of a newtype isn't known to be an object until it is converted
+                if matches!(tagging, Tagging::Internal { .. })
+                    && matches!(shape, Shape::Tuple(_) | Shape::Newtype(_))
+                {
+                    return Err(Error::new(
+                        variant.span(),
+                        "newtype and tuple variants can't be used in internally tagged enums, use a struct variant or add `content`",
+                    ));
+                }
+
+                variants.push(Variant {
+                    ident: variant.ident.clone(),
+                    name: name.unwrap_or_else(|| match rename_all {
+                        Some(rule) => rule.apply(&ident),
+                        None => ident,
+                    }),
+                    shape,
+                });
+            }
+
+            Ok(Body::Enum(tagging, variants))
+        }
+        Data::Union(data) => Err(Error::new(
+            data.union_token.span,
+            "unions can't be converted to and from JSON",
+        )),
+    }
+}
+
+fn shape(fields: &Fields, rename_all: Option<RenameRule>) -> Result<Shape, Error> {
+    let mut result = vec![];
+
+    for (i, field) in fields.iter().enumerate() {
+        let mut rename = None;
+        let mut default = None;
+        let mut flatten = false;
+
+        for attr in json_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    default = Some(if meta.input.peek(syn::Token![=]) {
+                        DefaultValue::Path(meta.value()?.parse::<LitStr>()?.parse()?)
+                    } else {
+                        DefaultValue::Trait
+                    });
+                } else if meta.path.is_ident("flatten") {
+                    flatten = true;
+                } else {
+                    return Err(meta.error("unknown json attribute"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        let (member, name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), ident.unraw().to_string()),
+            None => (Member::Unnamed(i.into()), i.to_string()),
+        };
+
+        if field.ident.is_none() && (rename.is_some() || default.is_some() || flatten) {
+            return Err(Error::new(
+                field.span(),
+                "`rename`, `default` and `flatten` can only be used on named fields",
+            ));
+        }
+
+        if flatten && (rename.is_some() || default.is_some()) {
+            return Err(Error::new(
+                field.span(),
+                "flattened fields have no key to `rename` or `default`",
+            ));
+        }
+
+        result.push(Field {
+            member,
+            binding: Ident::new(&format!("__field{}", i), field.span()),
+            key: rename.unwrap_or_else(|| match rename_all {
+                Some(rule) => rule.apply(&name),
+                None => name,
+            }),
+            default,
+            flatten,
+        });
+    }
+
+    Ok(match fields {
+        Fields::Unit => Shape::Unit,
+        Fields::Unnamed(_) if result.len() == 1 => Shape::Newtype(result.remove(0)),
+        Fields::Unnamed(_) => Shape::Tuple(result),
+        Fields::Named(_) => Shape::Named(result),
+    })
+}
+
+fn json_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("json"))
+}
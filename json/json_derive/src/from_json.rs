@@ -0,0 +1,271 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+use crate::attr::{Body, DefaultValue, Field, Shape, Tagging, Variant};
+
+/// The body of `FromJson::from_json` reading `value`.
+pub fn body(name: &Ident, body: &Body) -> TokenStream {
+    match body {
+        Body::Struct(shape) => content(shape, quote!(Self), &quote!(value), &[]),
+        Body::Enum(tagging, variants) => match tagging {
+            Tagging::External => external(variants),
+            Tagging::Internal { tag } => internal(tag, variants),
+            Tagging::Adjacent { tag, content } => adjacent(tag, content, variants),
+            Tagging::Untagged => untagged(name, variants),
+        },
+    }
+}
+
+/// An expression of `Result<Self, Error>` reading `shape` from `value`.
+///
+/// `exclude` are keys that belong to the parent, such as the tag of the enum, which
+/// shouldn't be passed to flattened fields.
+fn content(
+    shape: &Shape,
+    construct: TokenStream,
+    value: &TokenStream,
+    exclude: &[&str],
+) -> TokenStream {
+    match shape {
+        Shape::Unit => quote! {
+            <() as ::json::convert::FromJson>::from_json(#value)?;
+
+            ::core::result::Result::Ok(#construct)
+        },
+        Shape::Newtype(_) => quote! {
+            ::core::result::Result::Ok(#construct(::json::convert::FromJson::from_json(#value)?))
+        },
+        Shape::Tuple(fields) => {
+            let len = fields.len();
+            let elements =
+                (0..len).map(|i| quote!(::json::convert::__private::element(values, #i)?));
+
+            quote! {
+                let values = ::json::convert::__private::array(#value, #len)?;
+
+                ::core::result::Result::Ok(#construct(#(#elements),*))
+            }
+        }
+        Shape::Named(fields) => {
+            let object = named(fields, construct, exclude);
+
+            quote! {
+                let properties = ::json::convert::__private::object(#value)?;
+
+                ::core::result::Result::Ok(#object)
+            }
+        }
+    }
+}
+
+/// Builds `construct { ... }` from the object `properties`.
+fn named(fields: &[Field], construct: TokenStream, exclude: &[&str]) -> TokenStream {
+    let keys: Vec<&str> = fields
+        .iter()
+        .filter(|field| !field.flatten)
+        .map(|field| field.key.as_str())
+        .chain(exclude.iter().copied())
+        .collect();
+
+    let fields = fields.iter().map(|field| {
+        let member = &field.member;
+        let key = &field.key;
+
+        let value = if field.flatten {
+            quote! {
+                ::json::convert::FromJson::from_json(
+                    &::json::convert::__private::rest(properties, &[#(#keys),*])
+                )?
+            }
+        } else {
+            match &field.default {
+                Some(DefaultValue::Trait) => quote! {
+                    ::json::convert::__private::field_or_else(
+                        properties,
+                        #key,
+                        ::core::default::Default::default,
+                    )?
+                },
+                Some(DefaultValue::Path(path)) => quote! {
+                    ::json::convert::__private::field_or_else(properties, #key, #path)?
+                },
+                None => quote!(::json::convert::__private::field(properties, #key)?),
+            }
+        };
+
+        quote!(#member: #value)
+    });
+
+    quote!(#construct { #(#fields),* })
+}
+
+/// Reads the content of `variant` from `value` adding `segment` to the path of errors.
+fn scoped(variant: &Variant, value: &TokenStream, segment: TokenStream) -> TokenStream {
+    let ident = &variant.ident;
+    let content = content(&variant.shape, quote!(Self::#ident), value, &[]);
+
+    quote! {
+        ::json::convert::__private::scope(|| { #content })
+            .map_err(|err| err.prepend(::json::parser::value::Segment::Key(#segment)))
+    }
+}
+
+fn names(variants: &[Variant]) -> TokenStream {
+    let names = variants.iter().map(|variant| &variant.name);
+
+    quote!(&[#(#names),*])
+}
+
+/// `"Unit"` or `{ "Variant": content }`
+fn external(variants: &[Variant]) -> TokenStream {
+    let names = names(variants);
+
+    let strings = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let name = &variant.name;
+
+        match variant.shape {
+            Shape::Unit => quote!(#name => ::core::result::Result::Ok(Self::#ident)),
+            _ => quote! {
+                #name => ::core::result::Result::Err(::json::convert::Error::expected("object", value))
+            },
+        }
+    });
+
+    let objects = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let name = &variant.name;
+
+        match variant.shape {
+            // the content of unit variants is ignored
+            Shape::Unit => quote!(#name => ::core::result::Result::Ok(Self::#ident)),
+            _ => {
+                let content = scoped(variant, &quote!(content), quote!(variant.to_string()));
+
+                quote!(#name => #content)
+            }
+        }
+    });
+
+    quote! {
+        match value {
+            ::json::parser::Value::String(variant) => match variant.as_ref() {
+                #(#strings,)*
+                other => ::core::result::Result::Err(
+                    ::json::convert::Error::unknown_variant(other, #names)
+                ),
+            },
+            ::json::parser::Value::Object(properties) if properties.len() == 1 => {
+                let (variant, content) = properties.iter().next().unwrap();
+
+                match variant.as_ref() {
+                    #(#objects,)*
+                    other => ::core::result::Result::Err(
+                        ::json::convert::Error::unknown_variant(other, #names)
+                    ),
+                }
+            }
+            _ => ::core::result::Result::Err(
+                ::json::convert::Error::expected("string or object with a single property", value)
+            ),
+        }
+    }
+}
+
+/// `{ "tag": "Variant", ...fields }`
+fn internal(tag: &str, variants: &[Variant]) -> TokenStream {
+    let names = names(variants);
+
+    let arms = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let name = &variant.name;
+
+        let content = match &variant.shape {
+            Shape::Unit => quote!(::core::result::Result::Ok(Self::#ident)),
+            Shape::Named(fields) => {
+                let object = named(fields, quote!(Self::#ident), &[tag]);
+
+                quote!(::core::result::Result::Ok(#object))
+            }
+            Shape::Newtype(_) | Shape::Tuple(_) => {
+                unreachable!("rejected when parsing the attributes")
+            }
+        };
+
+        quote!(#name => #content)
+    });
+
+    quote! {
+        let properties = ::json::convert::__private::object(value)?;
+
+        match ::json::convert::__private::tag(properties, #tag)? {
+            #(#arms,)*
+            other => ::core::result::Result::Err(
+                ::json::convert::Error::unknown_variant(other, #names)
+                    .prepend(::json::parser::value::Segment::Key(#tag.to_string()))
+            ),
+        }
+    }
+}
+
+/// `{ "tag": "Variant", "content": content }`
+fn adjacent(tag: &str, content: &str, variants: &[Variant]) -> TokenStream {
+    let names = names(variants);
+
+    let arms = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let name = &variant.name;
+
+        match variant.shape {
+            Shape::Unit => quote!(#name => ::core::result::Result::Ok(Self::#ident)),
+            _ => {
+                let scoped = scoped(variant, &quote!(content), quote!(#content.to_string()));
+
+                quote! {
+                    #name => {
+                        let content = properties
+                            .get(#content)
+                            .ok_or_else(|| ::json::convert::Error::missing_field(#content))?;
+
+                        #scoped
+                    }
+                }
+            }
+        }
+    });
+
+    quote! {
+        let properties = ::json::convert::__private::object(value)?;
+
+        match ::json::convert::__private::tag(properties, #tag)? {
+            #(#arms,)*
+            other => ::core::result::Result::Err(
+                ::json::convert::Error::unknown_variant(other, #names)
+                    .prepend(::json::parser::value::Segment::Key(#tag.to_string()))
+            ),
+        }
+    }
+}
+
+/// Tries every variant in order.
+fn untagged(name: &Ident, variants: &[Variant]) -> TokenStream {
+    let attempts = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let content = content(&variant.shape, quote!(Self::#ident), &quote!(value), &[]);
+
+        quote! {
+            if let ::core::result::Result::Ok(value) = ::json::convert::__private::scope(|| { #content }) {
+                return ::core::result::Result::Ok(value);
+            }
+        }
+    });
+
+    let message = format!("data did not match any variant of untagged enum {}", name);
+
+    quote! {
+        #(#attempts)*
+
+        ::core::result::Result::Err(::json::convert::Error::new(#message))
+    }
+}
@@ -0,0 +1,72 @@
+//! `#[derive(FromJson, ToJson)]` for the `json` crate, enable its `derive` feature to use them.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, DeriveInput, Generics};
+
+mod attr;
+mod from_json;
+mod to_json;
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let body = match attr::parse(&input) {
+        Ok(body) => body,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let generics = bounded(&input.generics, quote!(::json::convert::FromJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = from_json::body(name, &body);
+
+    quote! {
+        impl #impl_generics ::json::convert::FromJson for #name #ty_generics #where_clause {
+            fn from_json(
+                value: &::json::parser::Value<'_>,
+            ) -> ::core::result::Result<Self, ::json::convert::Error> {
+                #body
+            }
+        }
+    }
+    .into()
+}
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let body = match attr::parse(&input) {
+        Ok(body) => body,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let generics = bounded(&input.generics, quote!(::json::convert::ToJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = to_json::body(&body);
+
+    quote! {
+        impl #impl_generics ::json::convert::ToJson for #name #ty_generics #where_clause {
+            fn to_json(&self) -> ::json::parser::Value<'static> {
+                #body
+            }
+        }
+    }
+    .into()
+}
+
+/// Adds `bound` to every type parameter.
+fn bounded(generics: &Generics, bound: proc_macro2::TokenStream) -> Generics {
+    let mut generics = generics.clone();
+
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+
+    generics
+}
@@ -0,0 +1,180 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::attr::{Body, Field, Shape, Tagging, Variant};
+
+/// The body of `ToJson::to_json`.
+pub fn body(body: &Body) -> TokenStream {
+    match body {
+        Body::Struct(shape) => content(shape, |field| {
+            let member = &field.member;
+
+            quote!(&self.#member)
+        }),
+        Body::Enum(tagging, variants) => {
+            let arms = variants.iter().map(|variant| {
+                let pattern = pattern(variant);
+                let value = variant_value(tagging, variant);
+
+                quote!(#pattern => #value)
+            });
+
+            quote! {
+                match self {
+                    #(#arms,)*
+                }
+            }
+        }
+    }
+}
+
+/// Binds every field of the variant to its `binding`.
+fn pattern(variant: &Variant) -> TokenStream {
+    let ident = &variant.ident;
+
+    match &variant.shape {
+        Shape::Unit => quote!(Self::#ident),
+        Shape::Newtype(field) => {
+            let binding = &field.binding;
+
+            quote!(Self::#ident(#binding))
+        }
+        Shape::Tuple(fields) => {
+            let bindings = fields.iter().map(|field| &field.binding);
+
+            quote!(Self::#ident(#(#bindings),*))
+        }
+        Shape::Named(fields) => {
+            let bindings = fields.iter().map(|field| {
+                let member = &field.member;
+                let binding = &field.binding;
+
+                quote!(#member: #binding)
+            });
+
+            quote!(Self::#ident { #(#bindings),* })
+        }
+    }
+}
+
+fn binding(field: &Field) -> TokenStream {
+    let binding = &field.binding;
+
+    quote!(#binding)
+}
+
+/// An expression of the value of `shape` where `access` is a reference to a field.
+fn content(shape: &Shape, access: impl Fn(&Field) -> TokenStream) -> TokenStream {
+    match shape {
+        Shape::Unit => quote!(::json::parser::Value::Null),
+        Shape::Newtype(field) => {
+            let field = access(field);
+
+            quote!(::json::convert::ToJson::to_json(#field))
+        }
+        Shape::Tuple(fields) => {
+            let fields = fields.iter().map(access);
+
+            quote! {
+                ::json::parser::Value::Array(::std::vec![
+                    #(::json::convert::ToJson::to_json(#fields)),*
+                ])
+            }
+        }
+        Shape::Named(fields) => {
+            let object = named(fields, access);
+
+            quote!({
+                let mut properties = ::json::parser::map::Map::new();
+
+                #object
+
+                ::json::parser::Value::Object(properties)
+            })
+        }
+    }
+}
+
+/// Inserts every field into `properties`.
+fn named(fields: &[Field], access: impl Fn(&Field) -> TokenStream) -> TokenStream {
+    let inserts = fields.iter().map(|field| {
+        let value = access(field);
+        let key = &field.key;
+
+        if field.flatten {
+            quote! {
+                ::json::convert::__private::flatten(
+                    &mut properties,
+                    ::json::convert::ToJson::to_json(#value),
+                );
+            }
+        } else {
+            quote! {
+                properties.insert(
+                    ::std::borrow::Cow::Borrowed(#key),
+                    ::json::convert::ToJson::to_json(#value),
+                );
+            }
+        }
+    });
+
+    quote!(#(#inserts)*)
+}
+
+/// `{ key: value }`
+fn single(key: &str, value: TokenStream) -> TokenStream {
+    quote!({
+        let mut properties = ::json::parser::map::Map::new();
+
+        properties.insert(::std::borrow::Cow::Borrowed(#key), #value);
+
+        ::json::parser::Value::Object(properties)
+    })
+}
+
+fn string(str: &str) -> TokenStream {
+    quote!(::json::parser::Value::String(::std::borrow::Cow::Borrowed(#str)))
+}
+
+fn variant_value(tagging: &Tagging, variant: &Variant) -> TokenStream {
+    let name = &variant.name;
+    let content = content(&variant.shape, binding);
+
+    match (tagging, &variant.shape) {
+        (Tagging::External, Shape::Unit) => string(name),
+        (Tagging::External, _) => single(name, content),
+        (Tagging::Internal { tag } | Tagging::Adjacent { tag, .. }, Shape::Unit) => {
+            single(tag, string(name))
+        }
+        (Tagging::Internal { tag }, Shape::Named(fields)) => {
+            let tag_value = string(name);
+            let object = named(fields, binding);
+
+            quote!({
+                let mut properties = ::json::parser::map::Map::new();
+
+                properties.insert(::std::borrow::Cow::Borrowed(#tag), #tag_value);
+
+                #object
+
+                ::json::parser::Value::Object(properties)
+            })
+        }
+        (Tagging::Internal { .. }, Shape::Newtype(_) | Shape::Tuple(_)) => {
+            unreachable!("rejected when parsing the attributes")
+        }
+        (Tagging::Adjacent { tag, content: key }, _) => {
+            let tag_value = string(name);
+
+            quote!({
+                let mut properties = ::json::parser::map::Map::new();
+
+                properties.insert(::std::borrow::Cow::Borrowed(#tag), #tag_value);
+                properties.insert(::std::borrow::Cow::Borrowed(#key), #content);
+
+                ::json::parser::Value::Object(properties)
+            })
+        }
+        (Tagging::Untagged, _) => content,
+    }
+}
@@ -0,0 +1,485 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    hash::BuildHasher,
+};
+
+use thiserror::Error;
+
+#[cfg(feature = "derive")]
+pub use json_derive::{FromJson, ToJson};
+
+use crate::parser::{
+    error::NumberError,
+    value::{PathDisplay, Segment},
+    Value,
+};
+
+/// Converts a type into a [`Value`] without serde.
+///
+/// With the `derive` feature `#[derive(ToJson)]` implements it for structs and enums,
+/// see [`FromJson`] for the attributes.
+pub trait ToJson {
+    fn to_json(&self) -> Value<'static>;
+}
+
+/// Converts a [`Value`] into a type without serde.
+///
+/// With the `derive` feature `#[derive(FromJson)]` implements it for structs and enums.
+/// The `#[json(...)]` attribute configures the conversion:
+///
+/// | Attribute                     | On                | Does                                                       |
+/// | ----------------------------- | ----------------- | ---------------------------------------------------------- |
+/// | `rename = "name"`             | field, variant    | Uses `name` as the key or variant name                     |
+/// | `rename_all = "camelCase"`    | struct, enum      | Renames every field of a struct or every variant of an enum |
+/// | `default`, `default = "path"` | field             | Uses `Default::default()` or `path()` when the key is missing |
+/// | `flatten`                     | field             | Reads and writes the field's properties in the parent object |
+/// | `tag = "type"`                | enum              | Writes the variant name in the `type` property of the variant's object |
+/// | `tag = "t", content = "c"`    | enum              | Writes `{ "t": variant, "c": content }`                    |
+/// | `untagged`                    | enum              | Writes only the content and tries every variant when reading |
+///
+/// Fields of type `Option<T>` are `None` when their key is missing.
+pub trait FromJson: Sized {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error>;
+
+    /// What a field is set to when its key is missing from the object, `None` if the
+    /// field is required.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// A value couldn't be converted, along with where it is from the top level value.
+///
+/// ```text
+/// Convert Error: $.server.port: expected integer, found string
+/// ```
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("Convert Error: {}: {message}", PathDisplay(.path))]
+pub struct Error {
+    pub message: String,
+    pub path: Vec<Segment>,
+}
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            path: vec![],
+        }
+    }
+
+    /// The value isn't the kind of value that was expected.
+    pub fn expected(expected: &str, found: &Value) -> Self {
+        Self::new(format!("expected {}, found {}", expected, found.kind()))
+    }
+
+    pub fn missing_field(key: &str) -> Self {
+        Self::new(format!("missing field `{}`", key))
+    }
+
+    pub fn unknown_variant(variant: &str, expected: &[&str]) -> Self {
+        let expected: Vec<String> = expected.iter().map(|v| format!("`{}`", v)).collect();
+
+        Self::new(format!(
+            "unknown variant `{}`, expected one of {}",
+            variant,
+            expected.join(", ")
+        ))
+    }
+
+    /// Adds the step into the child the error happened in to the front of the path.
+    pub fn prepend(mut self, segment: Segment) -> Self {
+        self.path.insert(0, segment);
+
+        self
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        value
+            .as_bool()
+            .ok_or_else(|| Error::expected("boolean", value))
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Value<'static> {
+        Value::Bool(*self)
+    }
+}
+
+macro_rules! integer {
+    ($($typ:ty => $to:ident,)*) => {
+        $(
+            impl FromJson for $typ {
+                fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+                    let num = value
+                        .as_number()
+                        .ok_or_else(|| Error::expected("integer", value))?;
+
+                    let out_of_range =
+                        || Error::new(format!("{} is out of range for {}", num, stringify!($typ)));
+
+                    match num.$to() {
+                        Ok(n) => <$typ>::try_from(n).map_err(|_| out_of_range()),
+                        Err(NumberError::NotAnInteger(_)) => {
+                            Err(Error::new(format!("expected integer, found {}", num)))
+                        }
+                        Err(_) => Err(out_of_range()),
+                    }
+                }
+            }
+
+            impl ToJson for $typ {
+                fn to_json(&self) -> Value<'static> {
+                    Value::from(*self)
+                }
+            }
+        )*
+    };
+}
+
+integer! {
+    i8 => to_i128,
+    i16 => to_i128,
+    i32 => to_i128,
+    i64 => to_i128,
+    i128 => to_i128,
+    isize => to_i128,
+    u8 => to_u128,
+    u16 => to_u128,
+    u32 => to_u128,
+    u64 => to_u128,
+    u128 => to_u128,
+    usize => to_u128,
+}
+
+/// Numbers too large for the type are an error instead of becoming infinity.
+impl FromJson for f64 {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        let num = value
+            .as_number()
+            .ok_or_else(|| Error::expected("number", value))?;
+
+        num.as_f64()
+            .filter(|f| f.is_finite())
+            .ok_or_else(|| Error::new(format!("{} is out of range for f64", num)))
+    }
+}
+
+/// `NaN` and infinities are written as `null`
+impl ToJson for f64 {
+    fn to_json(&self) -> Value<'static> {
+        Value::from(*self)
+    }
+}
+
+/// Numbers too large for the type are an error instead of becoming infinity.
+impl FromJson for f32 {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        let f = f64::from_json(value)? as f32;
+
+        if f.is_finite() {
+            Ok(f)
+        } else {
+            Err(Error::new(format!("{} is out of range for f32", value)))
+        }
+    }
+}
+
+/// `NaN` and infinities are written as `null`
+impl ToJson for f32 {
+    fn to_json(&self) -> Value<'static> {
+        Value::from(*self)
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        value
+            .as_str()
+            .map(|str| str.to_string())
+            .ok_or_else(|| Error::expected("string", value))
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Value<'static> {
+        Value::String(Cow::Owned(self.clone()))
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Value<'static> {
+        Value::String(Cow::Owned(self.to_string()))
+    }
+}
+
+impl FromJson for char {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        let str = value
+            .as_str()
+            .ok_or_else(|| Error::expected("character", value))?;
+
+        let mut chars = str.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(Error::new(format!(
+                "expected a single character, found '{}'",
+                str.escape_debug()
+            ))),
+        }
+    }
+}
+
+impl ToJson for char {
+    fn to_json(&self) -> Value<'static> {
+        Value::String(Cow::Owned(self.to_string()))
+    }
+}
+
+impl FromJson for () {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        match value {
+            Value::Null => Ok(()),
+            _ => Err(Error::expected("null", value)),
+        }
+    }
+}
+
+impl ToJson for () {
+    fn to_json(&self) -> Value<'static> {
+        Value::Null
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_json(value).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Value<'static> {
+        match self {
+            Some(value) => value.to_json(),
+            None => Value::Null,
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        T::from_json(value).map(Box::new)
+    }
+
+    fn from_missing() -> Option<Self> {
+        T::from_missing().map(Box::new)
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Value<'static> {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Value<'static> {
+        (**self).to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        let values = value
+            .as_array()
+            .ok_or_else(|| Error::expected("array", value))?;
+
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| T::from_json(value).map_err(|err| err.prepend(Segment::Index(i))))
+            .collect()
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Value<'static> {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Value<'static> {
+        Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+/// Converts every property of an object with `f`.
+fn properties<'v, T>(
+    value: &'v Value<'_>,
+    mut f: impl FnMut(&'v str, &'v Value<'_>) -> Result<T, Error>,
+) -> Result<Vec<T>, Error> {
+    let properties = value
+        .as_object()
+        .ok_or_else(|| Error::expected("object", value))?;
+
+    properties
+        .iter()
+        .map(|(key, value)| f(key, value).map_err(|err| err.prepend(Segment::Key(key.to_string()))))
+        .collect()
+}
+
+impl<V: FromJson, S: BuildHasher + Default> FromJson for HashMap<String, V, S> {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        let entries = properties(value, |key, value| {
+            Ok((key.to_string(), V::from_json(value)?))
+        })?;
+
+        Ok(entries.into_iter().collect())
+    }
+}
+
+impl<V: ToJson, S> ToJson for HashMap<String, V, S> {
+    fn to_json(&self) -> Value<'static> {
+        Value::Object(
+            self.iter()
+                .map(|(key, value)| (Cow::Owned(key.clone()), value.to_json()))
+                .collect(),
+        )
+    }
+}
+
+impl<V: FromJson> FromJson for BTreeMap<String, V> {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        let entries = properties(value, |key, value| {
+            Ok((key.to_string(), V::from_json(value)?))
+        })?;
+
+        Ok(entries.into_iter().collect())
+    }
+}
+
+impl<V: ToJson> ToJson for BTreeMap<String, V> {
+    fn to_json(&self) -> Value<'static> {
+        Value::Object(
+            self.iter()
+                .map(|(key, value)| (Cow::Owned(key.clone()), value.to_json()))
+                .collect(),
+        )
+    }
+}
+
+impl FromJson for Value<'static> {
+    fn from_json(value: &Value<'_>) -> Result<Self, Error> {
+        Ok(value.clone().into_owned())
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(Value::Null)
+    }
+}
+
+impl ToJson for Value<'_> {
+    fn to_json(&self) -> Value<'static> {
+        self.clone().into_owned()
+    }
+}
+
+/// Used by the code `#[derive(FromJson, ToJson)]` generates.
+#[doc(hidden)]
+pub mod __private {
+    use std::borrow::Cow;
+
+    use super::{Error, FromJson};
+    use crate::parser::{map::Map, value::Segment, Value};
+
+    pub type Properties<'v, 'a> = &'v Map<Cow<'a, str>, Value<'a>>;
+
+    /// Runs `f` so that the errors from every `?` in it can be added to at once.
+    pub fn scope<T>(f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+        f()
+    }
+
+    pub fn object<'v, 'a>(value: &'v Value<'a>) -> Result<Properties<'v, 'a>, Error> {
+        value
+            .as_object()
+            .ok_or_else(|| Error::expected("object", value))
+    }
+
+    pub fn array<'v, 'a>(value: &'v Value<'a>, len: usize) -> Result<&'v [Value<'a>], Error> {
+        match value.as_array() {
+            Some(values) if values.len() == len => Ok(values),
+            Some(values) => Err(Error::new(format!(
+                "expected an array of {} elements, found {}",
+                len,
+                values.len()
+            ))),
+            None => Err(Error::expected("array", value)),
+        }
+    }
+
+    pub fn element<T: FromJson>(values: &[Value<'_>], i: usize) -> Result<T, Error> {
+        T::from_json(&values[i]).map_err(|err| err.prepend(Segment::Index(i)))
+    }
+
+    pub fn field<T: FromJson>(properties: Properties, key: &str) -> Result<T, Error> {
+        match properties.get(key) {
+            Some(value) => {
+                T::from_json(value).map_err(|err| err.prepend(Segment::Key(key.to_string())))
+            }
+            None => T::from_missing().ok_or_else(|| Error::missing_field(key)),
+        }
+    }
+
+    pub fn field_or_else<T: FromJson>(
+        properties: Properties,
+        key: &str,
+        default: impl FnOnce() -> T,
+    ) -> Result<T, Error> {
+        match properties.get(key) {
+            Some(_) => field(properties, key),
+            None => Ok(default()),
+        }
+    }
+
+    /// The properties that aren't one of the fields of the struct, read by flattened fields.
+    pub fn rest(properties: Properties, keys: &[&str]) -> Value<'static> {
+        Value::Object(
+            properties
+                .iter()
+                .filter(|(key, _)| !keys.contains(&key.as_ref()))
+                .map(|(key, value)| (Cow::Owned(key.to_string()), value.clone().into_owned()))
+                .collect(),
+        )
+    }
+
+    /// Moves the properties of a flattened field into the object of its parent.
+    /// Values that aren't objects have no properties to add.
+    pub fn flatten(properties: &mut Map<Cow<'static, str>, Value<'static>>, value: Value<'static>) {
+        if let Value::Object(flattened) = value {
+            properties.extend(flattened);
+        }
+    }
+
+    /// Reads the name of the variant of an internally or adjacently tagged enum.
+    pub fn tag<'v>(properties: Properties<'v, '_>, tag: &str) -> Result<&'v str, Error> {
+        match properties.get(tag) {
+            Some(value) => value.as_str().ok_or_else(|| {
+                Error::expected("string", value).prepend(Segment::Key(tag.to_string()))
+            }),
+            None => Err(Error::missing_field(tag)),
+        }
+    }
+}
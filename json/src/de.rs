@@ -0,0 +1,658 @@
+use std::{borrow::Cow, fmt};
+
+use serde::{
+    de::{
+        self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+        Unexpected, VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize,
+};
+
+use crate::parser::{
+    error,
+    map::Map,
+    number::Number,
+    position::Position,
+    scanner::Scanner,
+    tokens::{Literal, Token, TokenType},
+    value::{PathDisplay, Segment},
+    DuplicateKeys, Options, Parser, Value,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The source isn't valid with the parser [`Options`]
+    Parse(error::Error),
+    /// The value doesn't match the type it is deserialized into
+    Data {
+        message: String,
+        /// Where the value is from the top level value
+        path: Vec<Segment>,
+        /// Where the value starts in the source, `None` when deserializing a [`Value`]
+        position: Option<Position>,
+    },
+}
+
+impl Error {
+    /// Adds the step into the child the error happened in to the front of the path.
+    fn prepend(mut self, segment: Segment) -> Self {
+        if let Self::Data { path, .. } = &mut self {
+            path.insert(0, segment);
+        }
+
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "{}", err),
+            Self::Data {
+                message,
+                path,
+                position,
+            } => {
+                write!(f, "Data Error: ")?;
+
+                if let Some(position) = position {
+                    write!(f, "{}: ", position)?;
+                }
+
+                write!(f, "{}: {}", PathDisplay(path), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(err) => Some(err),
+            Self::Data { .. } => None,
+        }
+    }
+}
+
+impl From<error::Error> for Error {
+    fn from(err: error::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Data {
+            message: msg.to_string(),
+            path: vec![],
+            position: None,
+        }
+    }
+}
+
+/// Deserializes `T` from `source` with the default (strict) [`Options`].
+pub fn from_str<'a, T: Deserialize<'a>>(source: &'a str) -> Result<T, Error> {
+    from_str_with(source, &Options::default())
+}
+
+/// Deserializes `T` from `source` parsed with `options`.
+pub fn from_str_with<'a, T: Deserialize<'a>>(
+    source: &'a str,
+    options: &Options,
+) -> Result<T, Error> {
+    from_parser(&mut Parser::new(options.clone()), source)
+}
+
+/// Deserializes `T` from `source` with an existing parser so that
+/// [`Parser::warnings`] can be read afterwards.
+///
+/// Errors from data that doesn't match `T` have the position of the value they are about.
+pub fn from_parser<'a, T: Deserialize<'a>>(
+    parser: &mut Parser<'a>,
+    source: &'a str,
+) -> Result<T, Error> {
+    let value = parser.parse(source)?;
+
+    T::deserialize(value).map_err(|err| match err {
+        Error::Data {
+            message,
+            path,
+            position: None,
+        } => {
+            let position = locate(source, &parser.options, &path);
+
+            Error::Data {
+                message,
+                path,
+                position,
+            }
+        }
+        err => err,
+    })
+}
+
+/// Deserializes `T` from a parsed value. Strings borrowed by `value` can be borrowed by `T`.
+pub fn from_value<'a, T: Deserialize<'a>>(value: Value<'a>) -> Result<T, Error> {
+    T::deserialize(value)
+}
+
+/// Finds where the value at `path` starts in `source`.
+///
+/// This only runs when deserializing fails so the source is scanned again instead of
+/// keeping the position of every value around.
+fn locate(source: &str, options: &Options, path: &[Segment]) -> Option<Position> {
+    let tokens = Scanner::new(options).scan(source).ok()?;
+
+    let mut current = 0;
+
+    for segment in path {
+        current = match (segment, tokens[current].typ) {
+            (Segment::Key(key), TokenType::LeftBrace) => {
+                find_property(&tokens, current, key, options.duplicate_keys)?
+            }
+            (Segment::Index(index), TokenType::LeftSquareBracket) => {
+                find_element(&tokens, current, *index)?
+            }
+            _ => return None,
+        };
+    }
+
+    Some(tokens[current].position)
+}
+
+/// Finds the start of the value of `key` in the object starting at `start`, picking the
+/// same one the parser kept when the key is there more than once.
+fn find_property(
+    tokens: &[Token],
+    start: usize,
+    key: &str,
+    duplicate_keys: DuplicateKeys,
+) -> Option<usize> {
+    let mut found = None;
+    let mut current = start + 1;
+
+    while tokens.get(current)?.typ != TokenType::RightBrace {
+        // key, colon then the value
+        let value = current + 2;
+
+        if matches!(&tokens[current].literal, Some(Literal::String(k)) if k == key) {
+            found = Some(value);
+
+            if !matches!(
+                duplicate_keys,
+                DuplicateKeys::LastWins | DuplicateKeys::Ignore
+            ) {
+                break;
+            }
+        }
+
+        current = skip_value(tokens, value)?;
+
+        if tokens[current].typ == TokenType::Comma {
+            current += 1;
+        }
+    }
+
+    found
+}
+
+/// Finds the start of the element at `index` in the array starting at `start`.
+fn find_element(tokens: &[Token], start: usize, index: usize) -> Option<usize> {
+    let mut current = start + 1;
+
+    for _ in 0..index {
+        if tokens.get(current)?.typ == TokenType::RightSquareBracket {
+            return None;
+        }
+
+        current = skip_value(tokens, current)?;
+
+        if tokens[current].typ == TokenType::Comma {
+            current += 1;
+        }
+    }
+
+    Some(current)
+}
+
+/// Returns the index of the token after the value starting at `start`.
+fn skip_value(tokens: &[Token], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut current = start;
+
+    loop {
+        match tokens.get(current)?.typ {
+            TokenType::LeftBrace | TokenType::LeftSquareBracket => depth += 1,
+            TokenType::RightBrace | TokenType::RightSquareBracket => depth -= 1,
+            TokenType::Eof => return None,
+            _ => {}
+        }
+
+        current += 1;
+
+        if depth == 0 {
+            return Some(current);
+        }
+    }
+}
+
+impl Value<'_> {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Value::Null => Unexpected::Unit,
+            Value::Bool(b) => Unexpected::Bool(*b),
+            Value::Number(num) => number_unexpected(num),
+            Value::String(str) => Unexpected::Str(str),
+            Value::Array(_) => Unexpected::Seq,
+            Value::Object(_) => Unexpected::Map,
+        }
+    }
+}
+
+fn number_unexpected(num: &Number) -> Unexpected<'static> {
+    if let Some(n) = num.as_u64() {
+        Unexpected::Unsigned(n)
+    } else if let Some(n) = num.as_i64() {
+        Unexpected::Signed(n)
+    } else {
+        Unexpected::Float(num.as_f64().unwrap_or(f64::NAN))
+    }
+}
+
+fn visit_number<'de, V: Visitor<'de>>(num: Number, visitor: V) -> Result<V::Value, Error> {
+    if let Some(n) = num.as_u64() {
+        return visitor.visit_u64(n);
+    }
+
+    if let Some(n) = num.as_i64() {
+        return visitor.visit_i64(n);
+    }
+
+    // integers kept by `arbitrary_precision` that need more than 64 bits
+    if num.is_decimal() {
+        if let Ok(n) = num.to_u128() {
+            return visitor.visit_u128(n);
+        }
+
+        if let Ok(n) = num.to_i128() {
+            return visitor.visit_i128(n);
+        }
+    }
+
+    match num.as_f64() {
+        Some(f) => visitor.visit_f64(f),
+        None => Err(de::Error::custom(format!(
+            "'{}' is out of range for f64",
+            num
+        ))),
+    }
+}
+
+impl<'de> Deserializer<'de> for Value<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(num) => visit_number(num, visitor),
+            Value::String(Cow::Borrowed(str)) => visitor.visit_borrowed_str(str),
+            Value::String(Cow::Owned(str)) => visitor.visit_string(str),
+            Value::Array(values) => visitor.visit_seq(SeqDeserializer {
+                iter: values.into_iter().enumerate(),
+            }),
+            Value::Object(properties) => visitor.visit_map(MapDeserializer {
+                iter: properties.into_iter(),
+                value: None,
+            }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants are strings and every other variant is an object with a single
+    /// property named after the variant.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Value::String(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                value: None,
+            }),
+            Value::Object(properties) if properties.len() == 1 => {
+                let (variant, value) = properties.into_iter().next().unwrap();
+
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            value => Err(de::Error::invalid_type(
+                value.unexpected(),
+                &"a string or an object with a single property",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+struct SeqDeserializer<'de> {
+    iter: std::iter::Enumerate<std::vec::IntoIter<Value<'de>>>,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.iter.next() {
+            Some((i, value)) => seed
+                .deserialize(value)
+                .map(Some)
+                .map_err(|err| err.prepend(Segment::Index(i))),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer<'de> {
+    iter: <Map<Cow<'de, str>, Value<'de>> as IntoIterator>::IntoIter,
+    /// The value of the key that was just deserialized along with the key for errors
+    value: Option<(Cow<'de, str>, Value<'de>)>,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((key, value)) = self.iter.next() else {
+            return Ok(None);
+        };
+
+        self.value = Some((key.clone(), value));
+
+        seed.deserialize(KeyDeserializer { key }).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let Some((key, value)) = self.value.take() else {
+            return Err(de::Error::custom("value is missing"));
+        };
+
+        seed.deserialize(value)
+            .map_err(|err| err.prepend(Segment::Key(key.into_owned())))
+    }
+}
+
+/// Deserializes object keys, keys can also be read as numbers for maps like `HashMap<u32, T>`.
+struct KeyDeserializer<'de> {
+    key: Cow<'de, str>,
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.key.parse() {
+                    Ok(n) => visitor.$visit(n),
+                    Err(_) => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.key {
+            Cow::Borrowed(key) => visitor.visit_borrowed_str(key),
+            Cow::Owned(key) => visitor.visit_string(key),
+        }
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(EnumDeserializer {
+            variant: self.key,
+            value: None,
+        })
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer<'de> {
+    variant: Cow<'de, str>,
+    /// `None` for unit variants written as a string
+    value: Option<Value<'de>>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer<'de> {
+    type Error = Error;
+    type Variant = VariantDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer<'de>), Error> {
+        let variant = seed.deserialize(KeyDeserializer {
+            key: self.variant.clone(),
+        })?;
+
+        Ok((
+            variant,
+            VariantDeserializer {
+                variant: self.variant,
+                value: self.value,
+            },
+        ))
+    }
+}
+
+struct VariantDeserializer<'de> {
+    variant: Cow<'de, str>,
+    value: Option<Value<'de>>,
+}
+
+impl<'de> VariantDeserializer<'de> {
+    /// Deserializes the content of the variant with `f` adding the variant to the path.
+    fn content<T>(
+        self,
+        expected: &'static str,
+        f: impl FnOnce(Value<'de>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        match self.value {
+            Some(value) => {
+                f(value).map_err(|err| err.prepend(Segment::Key(self.variant.into_owned())))
+            }
+            None => Err(de::Error::invalid_type(Unexpected::UnitVariant, &expected)),
+        }
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            None | Some(Value::Null) => Ok(()),
+            Some(value) => Err(de::Error::invalid_type(value.unexpected(), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        self.content("newtype variant", |value| seed.deserialize(value))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.content("tuple variant", |value| value.deserialize_seq(visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.content("struct variant", |value| value.deserialize_map(visitor))
+    }
+}
+
+impl<'de> Deserialize<'de> for Value<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value<'de>, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Value<'de>, E> {
+        Ok(Value::from(n))
+    }
+
+    fn visit_i128<E>(self, n: i128) -> Result<Value<'de>, E> {
+        Ok(Value::Number(Number::from(n)))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Value<'de>, E> {
+        Ok(Value::from(n))
+    }
+
+    fn visit_u128<E>(self, n: u128) -> Result<Value<'de>, E> {
+        Ok(Value::Number(Number::from(n)))
+    }
+
+    fn visit_f64<E>(self, f: f64) -> Result<Value<'de>, E> {
+        Ok(Value::from(f))
+    }
+
+    fn visit_borrowed_str<E>(self, str: &'de str) -> Result<Value<'de>, E> {
+        Ok(Value::String(Cow::Borrowed(str)))
+    }
+
+    fn visit_str<E>(self, str: &str) -> Result<Value<'de>, E> {
+        Ok(Value::String(Cow::Owned(str.to_string())))
+    }
+
+    fn visit_string<E>(self, str: String) -> Result<Value<'de>, E> {
+        Ok(Value::String(Cow::Owned(str)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value<'de>, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value<'de>, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value<'de>, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value<'de>, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value<'de>, A::Error> {
+        let mut properties = Map::new();
+
+        while let Some((key, value)) = map.next_entry::<Cow<'de, str>, Value<'de>>()? {
+            properties.insert(key, value);
+        }
+
+        Ok(Value::Object(properties))
+    }
+}
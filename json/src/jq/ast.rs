@@ -0,0 +1,96 @@
+use crate::parser::Value;
+
+#[derive(Debug, Clone)]
+pub(crate) enum Expr {
+    /// `.`
+    Identity,
+    /// `..`
+    Recurse,
+    Literal(Value<'static>),
+    /// A string with interpolations
+    Template(Vec<TemplatePart>),
+    /// `target[key]`, `.name` is the identity indexed by a string
+    Index(Box<Expr>, Box<Expr>),
+    /// `target[start:end]`
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    /// `target[]`
+    Iterate(Box<Expr>),
+    /// `target?`
+    Try(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    /// `a // b`
+    Alternative(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+    Negate(Box<Expr>),
+    /// `[]` or `[filter]`
+    Array(Option<Box<Expr>>),
+    /// The filters of the keys and values, `{name}` is already turned into `{name: .name}`
+    Object(Vec<(Expr, Expr)>),
+    /// The conditions and branches of `if` and `elif`, followed by `else` which is `.`
+    /// when it is left out
+    If(Vec<(Expr, Expr)>, Box<Expr>),
+    Call(Builtin, Vec<Expr>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum TemplatePart {
+    Text(String),
+    Interpolation(Expr),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Builtin {
+    Select,
+    Map,
+    Keys,
+    Length,
+    Not,
+    Empty,
+    Error,
+    Type,
+    Has,
+    Add,
+    ToString,
+    ToNumber,
+    Sort,
+}
+
+impl Builtin {
+    /// Looks up a builtin by its name and number of arguments.
+    pub(crate) fn from_name(name: &str, arity: usize) -> Option<Self> {
+        Some(match (name, arity) {
+            ("select", 1) => Self::Select,
+            ("map", 1) => Self::Map,
+            ("keys", 0) => Self::Keys,
+            ("length", 0) => Self::Length,
+            ("not", 0) => Self::Not,
+            ("empty", 0) => Self::Empty,
+            ("error", 0 | 1) => Self::Error,
+            ("type", 0) => Self::Type,
+            ("has", 1) => Self::Has,
+            ("add", 0) => Self::Add,
+            ("tostring", 0) => Self::ToString,
+            ("tonumber", 0) => Self::ToNumber,
+            ("sort", 0) => Self::Sort,
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,766 @@
+use std::{borrow::Cow, cmp::Ordering};
+
+use super::{
+    ast::{BinaryOp, Builtin, Expr, TemplatePart},
+    JqError,
+};
+use crate::parser::{map::Map, number::Number, Value};
+
+type Outputs<'a> = Result<Vec<Value<'a>>, JqError>;
+
+/// Runs `expr` on `input` pushing every output onto `outputs` as it is produced.
+///
+/// The outputs produced before an error stay in `outputs`, which is what lets `?` keep
+/// them.
+pub(crate) fn run<'a>(
+    expr: &Expr,
+    input: &Value<'a>,
+    outputs: &mut Vec<Value<'a>>,
+) -> Result<(), JqError> {
+    match expr {
+        Expr::Identity => outputs.push(input.clone()),
+        Expr::Recurse => recurse(input, outputs),
+        Expr::Literal(value) => outputs.push(value.clone()),
+        Expr::Template(parts) => outputs.extend(template(parts, input)?),
+        Expr::Index(target, key) => {
+            let keys = all(key, input)?;
+
+            each(target, input, |target| {
+                for key in &keys {
+                    outputs.push(index(&target, key)?);
+                }
+
+                Ok(())
+            })?;
+        }
+        Expr::Slice(target, start, end) => {
+            let starts = bound(start.as_deref(), input)?;
+            let ends = bound(end.as_deref(), input)?;
+
+            each(target, input, |target| {
+                for start in &starts {
+                    for end in &ends {
+                        outputs.push(slice(&target, start, end)?);
+                    }
+                }
+
+                Ok(())
+            })?;
+        }
+        Expr::Iterate(target) => {
+            each(target, input, |target| {
+                outputs.extend(iterate(&target)?);
+
+                Ok(())
+            })?;
+        }
+        // the outputs before the error are already in place
+        Expr::Try(expr) => _ = run(expr, input, outputs),
+        Expr::Pipe(left, right) => each(left, input, |value| run(right, &value, outputs))?,
+        Expr::Comma(left, right) => {
+            run(left, input, outputs)?;
+            run(right, input, outputs)?;
+        }
+        Expr::Alternative(left, right) => {
+            let mut values = vec![];
+
+            // an error on the left only ends its outputs
+            _ = run(left, input, &mut values);
+
+            values.retain(is_truthy);
+
+            if values.is_empty() {
+                return run(right, input, outputs);
+            }
+
+            outputs.extend(values);
+        }
+        Expr::And(left, right) => each(left, input, |value| {
+            if !is_truthy(&value) {
+                outputs.push(Value::Bool(false));
+
+                return Ok(());
+            }
+
+            each(right, input, |value| {
+                outputs.push(Value::Bool(is_truthy(&value)));
+
+                Ok(())
+            })
+        })?,
+        Expr::Or(left, right) => each(left, input, |value| {
+            if is_truthy(&value) {
+                outputs.push(Value::Bool(true));
+
+                return Ok(());
+            }
+
+            each(right, input, |value| {
+                outputs.push(Value::Bool(is_truthy(&value)));
+
+                Ok(())
+            })
+        })?,
+        Expr::Binary(left, op, right) => {
+            let lefts = all(left, input)?;
+
+            // like jq the right side is the outer loop
+            each(right, input, |right| {
+                for left in &lefts {
+                    outputs.push(binary(left, *op, &right)?);
+                }
+
+                Ok(())
+            })?;
+        }
+        Expr::Negate(expr) => each(expr, input, |value| {
+            let negated = match value {
+                Value::Number(n) => {
+                    arithmetic(&Number::from(0), &n, i64::checked_sub, |a, b| a - b)?
+                }
+                value => {
+                    return Err(JqError::Runtime(format!(
+                        "{} cannot be negated.",
+                        describe(&value)
+                    )))
+                }
+            };
+
+            outputs.push(negated);
+
+            Ok(())
+        })?,
+        Expr::Array(None) => outputs.push(Value::Array(vec![])),
+        Expr::Array(Some(expr)) => outputs.push(Value::Array(all(expr, input)?)),
+        Expr::Object(entries) => outputs.extend(object(entries, input)?),
+        Expr::If(branches, otherwise) => if_else(branches, otherwise, input, outputs)?,
+        Expr::Call(builtin, args) => call(*builtin, args, input, outputs)?,
+    }
+
+    Ok(())
+}
+
+/// Collects every output of `expr`, failing on the first error.
+fn all<'a>(expr: &Expr, input: &Value<'a>) -> Outputs<'a> {
+    let mut outputs = vec![];
+
+    run(expr, input, &mut outputs)?;
+
+    Ok(outputs)
+}
+
+/// Calls `f` with every output of `expr`. When `expr` fails `f` still gets the outputs
+/// before the error, then the error is returned.
+fn each<'a>(
+    expr: &Expr,
+    input: &Value<'a>,
+    mut f: impl FnMut(Value<'a>) -> Result<(), JqError>,
+) -> Result<(), JqError> {
+    let mut values = vec![];
+
+    let result = run(expr, input, &mut values);
+
+    for value in values {
+        f(value)?;
+    }
+
+    result
+}
+
+/// Every value inside `value` including itself, parents before their children.
+fn recurse<'a>(value: &Value<'a>, outputs: &mut Vec<Value<'a>>) {
+    outputs.push(value.clone());
+
+    match value {
+        Value::Array(values) => values.iter().for_each(|value| recurse(value, outputs)),
+        Value::Object(properties) => properties
+            .values()
+            .for_each(|value| recurse(value, outputs)),
+        _ => {}
+    }
+}
+
+/// Every combination of the interpolated outputs, the earlier ones change slowest.
+fn template<'a>(parts: &[TemplatePart], input: &Value<'a>) -> Outputs<'a> {
+    let mut strings = vec![String::new()];
+
+    for part in parts {
+        match part {
+            TemplatePart::Text(text) => strings.iter_mut().for_each(|str| str.push_str(text)),
+            TemplatePart::Interpolation(expr) => {
+                let values = all(expr, input)?;
+
+                strings = strings
+                    .iter()
+                    .flat_map(|str| {
+                        values
+                            .iter()
+                            .map(move |value| str.clone() + &to_text(value))
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    Ok(strings.into_iter().map(Value::from).collect())
+}
+
+fn index<'a>(target: &Value<'a>, key: &Value<'a>) -> Result<Value<'a>, JqError> {
+    match (target, key) {
+        (Value::Object(properties), Value::String(key)) => {
+            Ok(properties.get(key.as_ref()).cloned().unwrap_or(Value::Null))
+        }
+        (Value::Array(values), Value::Number(n)) => {
+            let i = n.as_f64().unwrap_or_default().floor();
+            // negative indices count from the end
+            let i = if i < 0.0 { i + values.len() as f64 } else { i };
+
+            if i < 0.0 {
+                return Ok(Value::Null);
+            }
+
+            Ok(values.get(i as usize).cloned().unwrap_or(Value::Null))
+        }
+        (Value::Null, Value::String(_) | Value::Number(_)) => Ok(Value::Null),
+        (target, Value::String(key)) => Err(JqError::Runtime(format!(
+            "Cannot index {} with \"{}\".",
+            target.kind(),
+            key
+        ))),
+        (target, key) => Err(JqError::Runtime(format!(
+            "Cannot index {} with {}.",
+            target.kind(),
+            key.kind()
+        ))),
+    }
+}
+
+/// The outputs of the start or end of a slice, `null` when it is left out.
+fn bound<'a>(expr: Option<&Expr>, input: &Value<'a>) -> Outputs<'a> {
+    match expr {
+        Some(expr) => all(expr, input),
+        None => Ok(vec![Value::Null]),
+    }
+}
+
+fn slice<'a>(target: &Value<'a>, start: &Value<'a>, end: &Value<'a>) -> Result<Value<'a>, JqError> {
+    let len = match target {
+        Value::Null => return Ok(Value::Null),
+        Value::Array(values) => values.len(),
+        Value::String(str) => str.chars().count(),
+        target => {
+            return Err(JqError::Runtime(format!(
+                "Cannot slice {}.",
+                describe(target)
+            )))
+        }
+    };
+
+    let bound = |value: &Value<'a>, default: usize| match value {
+        Value::Null => Ok(default),
+        Value::Number(n) => {
+            let i = n.as_f64().unwrap_or_default().floor();
+            let i = if i < 0.0 { i + len as f64 } else { i };
+
+            Ok(i.clamp(0.0, len as f64) as usize)
+        }
+        value => Err(JqError::Runtime(format!(
+            "Start and end indices of a slice must be numbers, found {}.",
+            value.kind()
+        ))),
+    };
+
+    let start = bound(start, 0)?;
+    let end = bound(end, len)?.max(start);
+
+    Ok(match target {
+        Value::Array(values) => Value::Array(values[start..end].to_vec()),
+        Value::String(str) => Value::from(
+            str.chars()
+                .skip(start)
+                .take(end - start)
+                .collect::<String>(),
+        ),
+        _ => unreachable!(),
+    })
+}
+
+fn iterate<'a>(target: &Value<'a>) -> Outputs<'a> {
+    match target {
+        Value::Array(values) => Ok(values.clone()),
+        Value::Object(properties) => Ok(properties.values().cloned().collect()),
+        target => Err(JqError::Runtime(format!(
+            "Cannot iterate over {}.",
+            describe(target)
+        ))),
+    }
+}
+
+/// Every combination of the outputs of the keys and values, the first entry changes
+/// slowest.
+fn object<'a>(entries: &[(Expr, Expr)], input: &Value<'a>) -> Outputs<'a> {
+    let mut objects = vec![Map::new()];
+
+    for (key, value) in entries {
+        let keys = all(key, input)?;
+        let values = all(value, input)?;
+        let mut next = vec![];
+
+        for object in &objects {
+            for key in &keys {
+                let Value::String(key) = key else {
+                    return Err(JqError::Runtime(format!(
+                        "Object keys must be strings, found {}.",
+                        describe(key)
+                    )));
+                };
+
+                for value in &values {
+                    let mut object = object.clone();
+
+                    object.insert(key.clone(), value.clone());
+                    next.push(object);
+                }
+            }
+        }
+
+        objects = next;
+    }
+
+    Ok(objects.into_iter().map(Value::Object).collect())
+}
+
+fn if_else<'a>(
+    branches: &[(Expr, Expr)],
+    otherwise: &Expr,
+    input: &Value<'a>,
+    outputs: &mut Vec<Value<'a>>,
+) -> Result<(), JqError> {
+    let Some(((condition, body), rest)) = branches.split_first() else {
+        return run(otherwise, input, outputs);
+    };
+
+    each(condition, input, |value| {
+        if is_truthy(&value) {
+            run(body, input, outputs)
+        } else {
+            if_else(rest, otherwise, input, outputs)
+        }
+    })
+}
+
+fn call<'a>(
+    builtin: Builtin,
+    args: &[Expr],
+    input: &Value<'a>,
+    outputs: &mut Vec<Value<'a>>,
+) -> Result<(), JqError> {
+    let output = match builtin {
+        Builtin::Select => {
+            return each(&args[0], input, |value| {
+                if is_truthy(&value) {
+                    outputs.push(input.clone());
+                }
+
+                Ok(())
+            })
+        }
+        Builtin::Map => {
+            let mut values = vec![];
+
+            for value in iterate(input)? {
+                run(&args[0], &value, &mut values)?;
+            }
+
+            Value::Array(values)
+        }
+        Builtin::Keys => match input {
+            Value::Object(properties) => {
+                let mut keys: Vec<_> = properties.keys().cloned().collect();
+
+                keys.sort();
+                keys.dedup();
+
+                Value::Array(keys.into_iter().map(Value::String).collect())
+            }
+            Value::Array(values) => Value::Array((0..values.len()).map(Value::from).collect()),
+            input => {
+                return Err(JqError::Runtime(format!(
+                    "{} has no keys.",
+                    describe(input)
+                )))
+            }
+        },
+        Builtin::Length => match input {
+            Value::Null => Value::from(0),
+            Value::Number(n) => number(n.as_f64().unwrap_or_default().abs())?,
+            Value::String(str) => Value::from(str.chars().count()),
+            Value::Array(values) => Value::from(values.len()),
+            Value::Object(properties) => Value::from(properties.len()),
+            input => {
+                return Err(JqError::Runtime(format!(
+                    "{} has no length.",
+                    describe(input)
+                )))
+            }
+        },
+        Builtin::Not => Value::Bool(!is_truthy(input)),
+        Builtin::Empty => return Ok(()),
+        Builtin::Error => match args.first() {
+            Some(message) => {
+                return each(message, input, |message| {
+                    Err(JqError::Runtime(to_text(&message)))
+                })
+            }
+            None => return Err(JqError::Runtime(to_text(input))),
+        },
+        Builtin::Type => Value::from(input.kind()),
+        Builtin::Has => {
+            return each(&args[0], input, |key| {
+                let has = match (input, &key) {
+                    (Value::Object(properties), Value::String(key)) => {
+                        properties.contains_key(key.as_ref())
+                    }
+                    (Value::Array(values), Value::Number(n)) => {
+                        let i = n.as_f64().unwrap_or_default();
+
+                        i >= 0.0 && i < values.len() as f64
+                    }
+                    (input, key) => {
+                        return Err(JqError::Runtime(format!(
+                            "Cannot check whether {} has a {} key.",
+                            input.kind(),
+                            key.kind()
+                        )))
+                    }
+                };
+
+                outputs.push(Value::Bool(has));
+
+                Ok(())
+            })
+        }
+        Builtin::Add => {
+            let mut sum = Value::Null;
+
+            if !input.is_null() {
+                for value in iterate(input)? {
+                    sum = binary(&sum, BinaryOp::Add, &value)?;
+                }
+            }
+
+            sum
+        }
+        Builtin::ToString => match input {
+            Value::String(_) => input.clone(),
+            input => Value::from(input.to_string()),
+        },
+        Builtin::ToNumber => match input {
+            Value::Number(_) => input.clone(),
+            Value::String(str) => match str.parse::<i64>() {
+                Ok(n) => Value::from(n),
+                Err(_) => str
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| {
+                        JqError::Runtime(format!("Cannot parse \"{}\" as a number.", str))
+                    })?,
+            },
+            input => {
+                return Err(JqError::Runtime(format!(
+                    "{} cannot be parsed as a number.",
+                    describe(input)
+                )))
+            }
+        },
+        Builtin::Sort => match input {
+            Value::Array(values) => {
+                let mut values = values.clone();
+
+                values.sort_by(compare);
+
+                Value::Array(values)
+            }
+            input => {
+                return Err(JqError::Runtime(format!(
+                    "{} cannot be sorted, as it is not an array.",
+                    describe(input)
+                )))
+            }
+        },
+    };
+
+    outputs.push(output);
+
+    Ok(())
+}
+
+fn binary<'a>(left: &Value<'a>, op: BinaryOp, right: &Value<'a>) -> Result<Value<'a>, JqError> {
+    match op {
+        BinaryOp::Add => add(left, right),
+        BinaryOp::Subtract => subtract(left, right),
+        BinaryOp::Multiply => multiply(left, right),
+        BinaryOp::Divide => divide(left, right),
+        BinaryOp::Modulo => modulo(left, right),
+        BinaryOp::Equal => Ok(Value::Bool(compare(left, right).is_eq())),
+        BinaryOp::NotEqual => Ok(Value::Bool(compare(left, right).is_ne())),
+        BinaryOp::Less => Ok(Value::Bool(compare(left, right).is_lt())),
+        BinaryOp::LessEqual => Ok(Value::Bool(compare(left, right).is_le())),
+        BinaryOp::Greater => Ok(Value::Bool(compare(left, right).is_gt())),
+        BinaryOp::GreaterEqual => Ok(Value::Bool(compare(left, right).is_ge())),
+    }
+}
+
+/// `null` is the identity, objects are merged with the right side winning.
+fn add<'a>(left: &Value<'a>, right: &Value<'a>) -> Result<Value<'a>, JqError> {
+    match (left, right) {
+        (Value::Null, value) | (value, Value::Null) => Ok(value.clone()),
+        (Value::Number(a), Value::Number(b)) => arithmetic(a, b, i64::checked_add, |a, b| a + b),
+        (Value::String(a), Value::String(b)) => Ok(Value::from(format!("{}{}", a, b))),
+        (Value::Array(a), Value::Array(b)) => {
+            Ok(Value::Array(a.iter().chain(b).cloned().collect()))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            let mut merged = a.clone();
+
+            for (key, value) in b {
+                merged.insert(key.clone(), value.clone());
+            }
+
+            Ok(Value::Object(merged))
+        }
+        (left, right) => Err(cannot(left, right, "added")),
+    }
+}
+
+/// Removes every element of the right array from the left one.
+fn subtract<'a>(left: &Value<'a>, right: &Value<'a>) -> Result<Value<'a>, JqError> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => arithmetic(a, b, i64::checked_sub, |a, b| a - b),
+        (Value::Array(a), Value::Array(b)) => Ok(Value::Array(
+            a.iter()
+                .filter(|value| !b.iter().any(|other| compare(value, other).is_eq()))
+                .cloned()
+                .collect(),
+        )),
+        (left, right) => Err(cannot(left, right, "subtracted")),
+    }
+}
+
+/// Repeats strings and merges objects recursively.
+fn multiply<'a>(left: &Value<'a>, right: &Value<'a>) -> Result<Value<'a>, JqError> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => arithmetic(a, b, i64::checked_mul, |a, b| a * b),
+        (Value::String(str), Value::Number(n)) | (Value::Number(n), Value::String(str)) => {
+            let times = n.as_f64().unwrap_or_default().trunc();
+
+            if times < 1.0 {
+                return Ok(Value::Null);
+            }
+
+            Ok(Value::from(str.repeat(times as usize)))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            let mut merged = a.clone();
+
+            for (key, value) in b {
+                let value = match (merged.get(key.as_ref()), value) {
+                    (Some(existing @ Value::Object(_)), Value::Object(_)) => {
+                        multiply(existing, value)?
+                    }
+                    _ => value.clone(),
+                };
+
+                merged.insert(key.clone(), value);
+            }
+
+            Ok(Value::Object(merged))
+        }
+        (left, right) => Err(cannot(left, right, "multiplied")),
+    }
+}
+
+/// Always divides as floats, dividing strings splits them.
+fn divide<'a>(left: &Value<'a>, right: &Value<'a>) -> Result<Value<'a>, JqError> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (
+                a.as_f64().unwrap_or_default(),
+                b.as_f64().unwrap_or_default(),
+            );
+
+            if b == 0.0 {
+                return Err(divisor_is_zero(left, right));
+            }
+
+            number(a / b)
+        }
+        (Value::String(str), Value::String(separator)) => {
+            let parts: Vec<_> = if str.is_empty() {
+                vec![]
+            } else if separator.is_empty() {
+                str.chars().map(|c| Value::from(c.to_string())).collect()
+            } else {
+                str.split(separator.as_ref())
+                    .map(|part| Value::from(part.to_string()))
+                    .collect()
+            };
+
+            Ok(Value::Array(parts))
+        }
+        (left, right) => Err(cannot(left, right, "divided")),
+    }
+}
+
+/// Both sides are truncated to integers first.
+fn modulo<'a>(left: &Value<'a>, right: &Value<'a>) -> Result<Value<'a>, JqError> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => {
+            let a = a.as_f64().unwrap_or_default() as i64;
+            let b = b.as_f64().unwrap_or_default() as i64;
+
+            if b == 0 {
+                return Err(divisor_is_zero(left, right));
+            }
+
+            // only `i64::MIN % -1` overflows
+            Ok(Value::from(a.checked_rem(b).unwrap_or(0)))
+        }
+        (left, right) => Err(cannot(left, right, "divided")),
+    }
+}
+
+/// Integers stay exact unless they overflow, which falls back to floats.
+fn arithmetic<'a>(
+    a: &Number,
+    b: &Number,
+    integer: fn(i64, i64) -> Option<i64>,
+    float: fn(f64, f64) -> f64,
+) -> Result<Value<'a>, JqError> {
+    if let (Some(a), Some(b)) = (a.as_i64(), b.as_i64()) {
+        if let Some(n) = integer(a, b) {
+            return Ok(Value::from(n));
+        }
+    }
+
+    number(float(
+        a.as_f64().unwrap_or_default(),
+        b.as_f64().unwrap_or_default(),
+    ))
+}
+
+/// Turns a float result into a number, keeping integral results as integers so `4 / 2`
+/// is written as `2`.
+fn number<'a>(f: f64) -> Result<Value<'a>, JqError> {
+    // 2^53, above it not every integer can be represented
+    if f.fract() == 0.0 && f.abs() < 9007199254740992.0 {
+        return Ok(Value::from(f as i64));
+    }
+
+    Number::from_f64(f).map(Value::Number).ok_or_else(|| {
+        JqError::Runtime("The result of the arithmetic is out of range.".to_string())
+    })
+}
+
+/// `null` < `false` < `true` < numbers < strings < arrays < objects. Objects compare
+/// their sorted keys first and then their values key by key.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.compare(b).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => {
+            for (a, b) in a.iter().zip(b) {
+                let ordering = compare(a, b);
+
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            }
+
+            a.len().cmp(&b.len())
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            let mut a_keys: Vec<&Cow<str>> = a.keys().collect();
+            let mut b_keys: Vec<&Cow<str>> = b.keys().collect();
+
+            a_keys.sort();
+            b_keys.sort();
+
+            let ordering = a_keys.cmp(&b_keys);
+
+            if ordering.is_ne() {
+                return ordering;
+            }
+
+            for key in a_keys {
+                let ordering = compare(&a[key.as_ref()], &b[key.as_ref()]);
+
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            }
+
+            Ordering::Equal
+        }
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// `false` and `null` are false, everything else is true.
+fn is_truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// Strings are interpolated as they are, anything else as JSON.
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(str) => str.to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// The kind of a value along with the start of its JSON for error messages.
+fn describe(value: &Value) -> String {
+    const MAX: usize = 11;
+
+    let json = value.to_string();
+
+    if json.chars().count() > MAX {
+        let start: String = json.chars().take(MAX - 1).collect();
+
+        format!("{} ({}...)", value.kind(), start)
+    } else {
+        format!("{} ({})", value.kind(), json)
+    }
+}
+
+fn cannot(left: &Value, right: &Value, action: &str) -> JqError {
+    JqError::Runtime(format!(
+        "{} and {} cannot be {}.",
+        describe(left),
+        describe(right),
+        action
+    ))
+}
+
+fn divisor_is_zero(left: &Value, right: &Value) -> JqError {
+    JqError::Runtime(format!(
+        "{} and {} cannot be divided because the divisor is zero.",
+        describe(left),
+        describe(right)
+    ))
+}
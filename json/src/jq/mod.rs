@@ -0,0 +1,180 @@
+//! A subset of the jq filter language.
+//!
+//! A filter is compiled once with [`Filter::parse`] and can then be run against any
+//! number of values, producing zero or more outputs for each.
+//!
+//! Supported are `.`, `..`, `.foo`, `."foo"`, `.[n]`, `.[a:b]`, `.[]`, `?`, `|`, `,`,
+//! array and object construction, string interpolation, `+ - * / %`, comparisons,
+//! `and`, `or`, `//`, `if ... then ... elif ... else ... end` and the builtins
+//! `select`, `map`, `keys`, `length`, `not`, `empty`, `error`, `type`, `has`, `add`,
+//! `tostring`, `tonumber` and `sort`.
+//!
+//! ```text
+//! let filter = Filter::parse(r#".servers[] | select(.port > 8000) | "\(.host):\(.port)""#)?;
+//!
+//! for output in filter.run(&value)? {
+//!     println!("{}", output);
+//! }
+//!
+//! // "web-2:8080"
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::parser::{position::Position, Value};
+
+mod ast;
+mod eval;
+mod parser;
+mod scanner;
+mod tokens;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum JqError {
+    /// The filter isn't valid
+    #[error("Syntax Error: {position}: {message}")]
+    Syntax {
+        message: String,
+        /// Where in the filter, columns are counted in chars
+        position: Position,
+    },
+    /// The filter failed on the input, like indexing a number with a string
+    #[error("Filter Error: {0}")]
+    Runtime(String),
+}
+
+impl JqError {
+    pub(crate) fn syntax(message: impl Into<String>, position: Position) -> Self {
+        Self::Syntax {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+/// A compiled jq filter.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    source: String,
+    expr: ast::Expr,
+}
+
+impl Filter {
+    pub fn parse(filter: &str) -> Result<Self, JqError> {
+        let tokens = scanner::Scanner::new(filter).scan()?;
+
+        Ok(Self {
+            source: filter.to_string(),
+            expr: parser::Parser::new(tokens).parse()?,
+        })
+    }
+
+    /// Runs the filter returning every output in order. The first error stops the run
+    /// unless it is caught by `?` or `//`.
+    pub fn run<'a>(&self, input: &Value<'a>) -> Result<Vec<Value<'a>>, JqError> {
+        let mut outputs = vec![];
+
+        eval::run(&self.expr, input, &mut outputs)?;
+
+        Ok(outputs)
+    }
+
+    /// The text the filter was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = JqError;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        Self::parse(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn run<'a>(filter: &str, input: &Value<'a>) -> Result<Vec<Value<'a>>, JqError> {
+        Filter::parse(filter).unwrap().run(input)
+    }
+
+    #[test]
+    fn try_keeps_the_outputs_around_a_caught_error() {
+        let input = json!(null);
+
+        assert_eq!(
+            run(r#"1, error("x")?, 2"#, &input),
+            Ok(vec![json!(1), json!(2)])
+        );
+        assert_eq!(
+            run(r#"[(1, error("x"), 2)?]"#, &input),
+            Ok(vec![json!([1])])
+        );
+    }
+
+    #[test]
+    fn try_does_not_catch_errors_after_it() {
+        let input = json!([1]);
+
+        assert_eq!(
+            run(r#".[]? | error("y")"#, &input),
+            Err(JqError::Runtime("y".to_string()))
+        );
+    }
+
+    #[test]
+    fn error_is_uncaught_without_try() {
+        let input = json!({ "a": 1 });
+
+        assert_eq!(
+            run(r#"error("x")"#, &input),
+            Err(JqError::Runtime("x".to_string()))
+        );
+        assert_eq!(
+            run(".a | error", &input),
+            Err(JqError::Runtime("1".to_string()))
+        );
+    }
+
+    #[test]
+    fn alternative_replaces_empty_false_and_null() {
+        let input = json!(null);
+
+        assert_eq!(run("empty // 1", &input), Ok(vec![json!(1)]));
+        assert_eq!(run("false // 1", &input), Ok(vec![json!(1)]));
+        assert_eq!(run("null // 1", &input), Ok(vec![json!(1)]));
+        assert_eq!(
+            run("(false, 2, null, 3) // 4", &input),
+            Ok(vec![json!(2), json!(3)])
+        );
+        assert_eq!(run("0 // 1", &input), Ok(vec![json!(0)]));
+    }
+
+    #[test]
+    fn alternative_ignores_errors_on_the_left() {
+        let input = json!(null);
+
+        assert_eq!(run(r#"error("x") // 1"#, &input), Ok(vec![json!(1)]));
+        assert_eq!(run(r#"(1, error("x")) // 5"#, &input), Ok(vec![json!(1)]));
+    }
+
+    #[test]
+    fn iterate_with_try() {
+        assert_eq!(run(".[]?", &json!([1, 2])), Ok(vec![json!(1), json!(2)]));
+        assert_eq!(run(".[]?", &json!({ "a": true })), Ok(vec![json!(true)]));
+        assert_eq!(run(".[]?", &json!(3)), Ok(vec![]));
+        assert!(run(".[]", &json!(3)).is_err());
+    }
+}
@@ -0,0 +1,593 @@
+use std::borrow::Cow;
+
+use super::{
+    ast::{BinaryOp, Builtin, Expr, TemplatePart},
+    tokens::{Literal, Part, Token, TokenType},
+    JqError,
+};
+use crate::parser::Value;
+
+/// Parses the tokens of a filter, from the lowest precedence to the highest:
+/// `|`, `,`, `//`, `or`, `and`, comparisons, `+` `-`, `*` `/` `%`, unary `-` and
+/// postfix indexing.
+pub(crate) struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    current: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(tokens: Vec<Token<'a>>) -> Self {
+        Self { tokens, current: 0 }
+    }
+
+    /// Parses the whole filter, anything left after it is an error.
+    pub(crate) fn parse(&mut self) -> Result<Expr, JqError> {
+        let expr = self.pipe()?;
+
+        if !self.is_at_end() {
+            return Err(JqError::syntax(
+                format!("Unexpected {}.", self.found()),
+                self.peek().position,
+            ));
+        }
+
+        Ok(expr)
+    }
+
+    fn pipe(&mut self) -> Result<Expr, JqError> {
+        let left = self.comma()?;
+
+        if self.mat(&[TokenType::Pipe]) {
+            // right associative
+            let right = self.pipe()?;
+
+            return Ok(Expr::Pipe(Box::new(left), Box::new(right)));
+        }
+
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.alternative()?;
+
+        while self.mat(&[TokenType::Comma]) {
+            let right = self.alternative()?;
+
+            expr = Expr::Comma(Box::new(expr), Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn alternative(&mut self) -> Result<Expr, JqError> {
+        let left = self.or()?;
+
+        if self.mat(&[TokenType::SlashSlash]) {
+            // right associative
+            let right = self.alternative()?;
+
+            return Ok(Expr::Alternative(Box::new(left), Box::new(right)));
+        }
+
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.and()?;
+
+        while self.mat(&[TokenType::Or]) {
+            let right = self.and()?;
+
+            expr = Expr::Or(Box::new(expr), Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.comparison()?;
+
+        while self.mat(&[TokenType::And]) {
+            let right = self.comparison()?;
+
+            expr = Expr::And(Box::new(expr), Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    /// Comparisons don't chain, `a < b < c` is an error.
+    fn comparison(&mut self) -> Result<Expr, JqError> {
+        let left = self.additive()?;
+
+        let op = match self.peek().typ {
+            TokenType::EqualEqual => BinaryOp::Equal,
+            TokenType::BangEqual => BinaryOp::NotEqual,
+            TokenType::Less => BinaryOp::Less,
+            TokenType::LessEqual => BinaryOp::LessEqual,
+            TokenType::Greater => BinaryOp::Greater,
+            TokenType::GreaterEqual => BinaryOp::GreaterEqual,
+            _ => return Ok(left),
+        };
+
+        self.advance();
+
+        let right = self.additive()?;
+
+        Ok(Expr::Binary(Box::new(left), op, Box::new(right)))
+    }
+
+    fn additive(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.multiplicative()?;
+
+        loop {
+            let op = match self.peek().typ {
+                TokenType::Plus => BinaryOp::Add,
+                TokenType::Minus => BinaryOp::Subtract,
+                _ => return Ok(expr),
+            };
+
+            self.advance();
+
+            let right = self.multiplicative()?;
+
+            expr = Expr::Binary(Box::new(expr), op, Box::new(right));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, JqError> {
+        let mut expr = self.unary()?;
+
+        loop {
+            let op = match self.peek().typ {
+                TokenType::Star => BinaryOp::Multiply,
+                TokenType::Slash => BinaryOp::Divide,
+                TokenType::Percent => BinaryOp::Modulo,
+                _ => return Ok(expr),
+            };
+
+            self.advance();
+
+            let right = self.unary()?;
+
+            expr = Expr::Binary(Box::new(expr), op, Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, JqError> {
+        if self.mat(&[TokenType::Minus]) {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+
+        let term = self.term()?;
+
+        self.postfix(term)
+    }
+
+    /// Indexing, iterating, slicing and `?` after a term.
+    fn postfix(&mut self, mut expr: Expr) -> Result<Expr, JqError> {
+        loop {
+            if self.mat(&[TokenType::Field]) {
+                let name = self.take_string();
+
+                expr = Expr::Index(Box::new(expr), Box::new(string(name)));
+            } else if self.check(TokenType::Dot) && self.check_next(TokenType::String) {
+                self.advance();
+
+                let key = self.string()?;
+
+                expr = Expr::Index(Box::new(expr), Box::new(key));
+            } else if self.check(TokenType::LeftSquareBracket)
+                || (self.check(TokenType::Dot) && self.check_next(TokenType::LeftSquareBracket))
+            {
+                self.mat(&[TokenType::Dot]);
+                self.advance();
+
+                expr = self.bracket(expr)?;
+            } else if self.mat(&[TokenType::Question]) {
+                expr = Expr::Try(Box::new(expr));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// `[]`, `[key]` or `[start:end]` after the `[`.
+    fn bracket(&mut self, target: Expr) -> Result<Expr, JqError> {
+        let target = Box::new(target);
+
+        if self.mat(&[TokenType::RightSquareBracket]) {
+            return Ok(Expr::Iterate(target));
+        }
+
+        let start = if self.check(TokenType::Colon) {
+            None
+        } else {
+            Some(Box::new(self.pipe()?))
+        };
+
+        let expr = if self.mat(&[TokenType::Colon]) {
+            let end = if self.check(TokenType::RightSquareBracket) {
+                None
+            } else {
+                Some(Box::new(self.pipe()?))
+            };
+
+            if start.is_none() && end.is_none() {
+                return Err(JqError::syntax(
+                    "A slice needs a start or an end.",
+                    self.previous().position,
+                ));
+            }
+
+            Expr::Slice(target, start, end)
+        } else {
+            // only missing when the slice starts with ':'
+            Expr::Index(target, start.unwrap())
+        };
+
+        self.consume(
+            TokenType::RightSquareBracket,
+            "Expected ']' after the index.",
+        )?;
+
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, JqError> {
+        let token = self.peek();
+        let position = token.position;
+
+        match token.typ {
+            TokenType::Dot => {
+                self.advance();
+
+                if self.check(TokenType::String) {
+                    let key = self.string()?;
+
+                    return Ok(Expr::Index(Box::new(Expr::Identity), Box::new(key)));
+                }
+
+                Ok(Expr::Identity)
+            }
+            TokenType::DotDot => {
+                self.advance();
+
+                Ok(Expr::Recurse)
+            }
+            TokenType::Field => {
+                self.advance();
+
+                let name = self.take_string();
+
+                Ok(Expr::Index(
+                    Box::new(Expr::Identity),
+                    Box::new(string(name)),
+                ))
+            }
+            TokenType::Number => {
+                self.advance();
+
+                match self.take_literal() {
+                    Some(Literal::Number(number)) => Ok(Expr::Literal(Value::Number(number))),
+                    _ => unreachable!(),
+                }
+            }
+            TokenType::String => self.string(),
+            TokenType::True => {
+                self.advance();
+
+                Ok(Expr::Literal(Value::Bool(true)))
+            }
+            TokenType::False => {
+                self.advance();
+
+                Ok(Expr::Literal(Value::Bool(false)))
+            }
+            TokenType::Null => {
+                self.advance();
+
+                Ok(Expr::Literal(Value::Null))
+            }
+            TokenType::LeftParen => {
+                self.advance();
+
+                let expr = self.pipe()?;
+
+                self.consume(TokenType::RightParen, "Expected ')' to close '('.")?;
+
+                Ok(expr)
+            }
+            TokenType::LeftSquareBracket => {
+                self.advance();
+
+                if self.mat(&[TokenType::RightSquareBracket]) {
+                    return Ok(Expr::Array(None));
+                }
+
+                let expr = self.pipe()?;
+
+                self.consume(
+                    TokenType::RightSquareBracket,
+                    "Expected ']' at the end of an array.",
+                )?;
+
+                Ok(Expr::Array(Some(Box::new(expr))))
+            }
+            TokenType::LeftBrace => {
+                self.advance();
+
+                self.object()
+            }
+            TokenType::If => {
+                self.advance();
+
+                self.if_expr()
+            }
+            TokenType::Identifier => {
+                self.advance();
+
+                let name = self.take_string();
+
+                self.call(&name, position)
+            }
+            _ => Err(JqError::syntax(
+                format!("Expected a filter, found {}.", self.found()),
+                position,
+            )),
+        }
+    }
+
+    /// A string literal or a string with interpolations.
+    fn string(&mut self) -> Result<Expr, JqError> {
+        self.consume(TokenType::String, "Expected a string.")?;
+
+        match self.take_literal() {
+            Some(Literal::String(str)) => Ok(string(str)),
+            Some(Literal::Template(parts)) => {
+                let mut result = vec![];
+
+                for part in parts {
+                    result.push(match part {
+                        Part::Text(text) => TemplatePart::Text(text),
+                        Part::Interpolation(tokens) => {
+                            TemplatePart::Interpolation(Parser::new(tokens).parse()?)
+                        }
+                    });
+                }
+
+                Ok(Expr::Template(result))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// The entries of an object after the `{`.
+    fn object(&mut self) -> Result<Expr, JqError> {
+        let mut entries = vec![];
+
+        if self.mat(&[TokenType::RightBrace]) {
+            return Ok(Expr::Object(entries));
+        }
+
+        loop {
+            let token = self.peek();
+
+            let (key, computed) = match token.typ {
+                // keywords are fine as keys
+                TokenType::Identifier
+                | TokenType::And
+                | TokenType::Or
+                | TokenType::If
+                | TokenType::Then
+                | TokenType::Elif
+                | TokenType::Else
+                | TokenType::End
+                | TokenType::True
+                | TokenType::False
+                | TokenType::Null => {
+                    let name = token.lexeme.to_string();
+
+                    self.advance();
+
+                    (string(name), false)
+                }
+                TokenType::String => (self.string()?, false),
+                TokenType::LeftParen => {
+                    self.advance();
+
+                    let key = self.pipe()?;
+
+                    self.consume(TokenType::RightParen, "Expected ')' after the key.")?;
+
+                    (key, true)
+                }
+                _ => {
+                    return Err(JqError::syntax(
+                        format!("Expected a key in the object, found {}.", self.found()),
+                        token.position,
+                    ))
+                }
+            };
+
+            let value = if self.mat(&[TokenType::Colon]) {
+                self.object_value()?
+            } else if computed {
+                return Err(JqError::syntax(
+                    "Expected ':' after a computed key.",
+                    self.peek().position,
+                ));
+            } else {
+                // `{name}` is short for `{name: .name}`
+                Expr::Index(Box::new(Expr::Identity), Box::new(key.clone()))
+            };
+
+            entries.push((key, value));
+
+            if self.mat(&[TokenType::Comma]) {
+                continue;
+            }
+
+            self.consume(
+                TokenType::RightBrace,
+                "Expected ',' or '}' after the value in the object.",
+            )?;
+
+            return Ok(Expr::Object(entries));
+        }
+    }
+
+    /// The value of an entry can be piped but `,` ends it.
+    fn object_value(&mut self) -> Result<Expr, JqError> {
+        let left = self.alternative()?;
+
+        if self.mat(&[TokenType::Pipe]) {
+            let right = self.object_value()?;
+
+            return Ok(Expr::Pipe(Box::new(left), Box::new(right)));
+        }
+
+        Ok(left)
+    }
+
+    /// `if cond then a elif cond then b else c end` after the `if`.
+    fn if_expr(&mut self) -> Result<Expr, JqError> {
+        let mut branches = vec![];
+
+        loop {
+            let condition = self.pipe()?;
+
+            self.consume(TokenType::Then, "Expected 'then' after the condition.")?;
+
+            let body = self.pipe()?;
+
+            branches.push((condition, body));
+
+            if !self.mat(&[TokenType::Elif]) {
+                break;
+            }
+        }
+
+        let otherwise = if self.mat(&[TokenType::Else]) {
+            self.pipe()?
+        } else {
+            Expr::Identity
+        };
+
+        self.consume(TokenType::End, "Expected 'end' to close 'if'.")?;
+
+        Ok(Expr::If(branches, Box::new(otherwise)))
+    }
+
+    /// A builtin with its arguments separated by `;`.
+    fn call(
+        &mut self,
+        name: &str,
+        position: crate::parser::position::Position,
+    ) -> Result<Expr, JqError> {
+        let mut args = vec![];
+
+        if self.mat(&[TokenType::LeftParen]) {
+            loop {
+                args.push(self.pipe()?);
+
+                if !self.mat(&[TokenType::Semicolon]) {
+                    break;
+                }
+            }
+
+            self.consume(TokenType::RightParen, "Expected ')' after the arguments.")?;
+        }
+
+        match Builtin::from_name(name, args.len()) {
+            Some(builtin) => Ok(Expr::Call(builtin, args)),
+            None => Err(JqError::syntax(
+                format!("Unknown function '{}/{}'.", name, args.len()),
+                position,
+            )),
+        }
+    }
+
+    /// Moves the literal out of the token that was just consumed.
+    fn take_literal(&mut self) -> Option<Literal<'a>> {
+        self.tokens[self.current - 1].literal.take()
+    }
+
+    /// The name of the field or identifier that was just consumed.
+    fn take_string(&mut self) -> String {
+        match self.take_literal() {
+            Some(Literal::String(str)) => str,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Describes the next token for error messages.
+    fn found(&self) -> String {
+        if self.peek().typ == TokenType::Eof && self.peek().lexeme == "\0" {
+            "the end of the filter".to_string()
+        } else {
+            format!("'{}'", self.peek().lexeme)
+        }
+    }
+
+    fn mat(&mut self, types: &[TokenType]) -> bool {
+        for typ in types {
+            if self.check(*typ) {
+                self.advance();
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn check(&self, typ: TokenType) -> bool {
+        if self.is_at_end() {
+            return false;
+        }
+
+        self.peek().typ == typ
+    }
+
+    fn check_next(&self, typ: TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.typ == typ)
+    }
+
+    fn peek(&self) -> &Token<'a> {
+        // the last token is always Eof which is never advanced past
+        self.tokens.get(self.current).unwrap()
+    }
+
+    fn previous(&self) -> &Token<'a> {
+        self.tokens.get(self.current - 1).unwrap()
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.peek().typ == TokenType::Eof
+    }
+
+    fn advance(&mut self) -> &Token<'a> {
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+
+        self.previous()
+    }
+
+    fn consume(&mut self, typ: TokenType, msg: &str) -> Result<&Token<'a>, JqError> {
+        if self.check(typ) {
+            return Ok(self.advance());
+        }
+
+        Err(JqError::syntax(
+            format!("{} Found {}.", msg, self.found()),
+            self.peek().position,
+        ))
+    }
+}
+
+fn string(str: String) -> Expr {
+    Expr::Literal(Value::String(Cow::Owned(str)))
+}
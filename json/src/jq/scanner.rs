@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+
+use super::{
+    tokens::{keywords, Literal, Part, Token, TokenType},
+    JqError,
+};
+use crate::parser::{number::Number, position::Position};
+
+/// Turns the text of a filter into [`Token`]s.
+///
+/// Strings with interpolations are scanned into a [`Literal::Template`] holding the
+/// tokens of every interpolated filter, so the parser never has to look at the text.
+pub(crate) struct Scanner<'a> {
+    source: &'a str,
+    current: usize,
+    start: usize,
+    line: usize,
+    column: usize,
+    keywords: HashMap<&'static str, TokenType>,
+}
+
+impl<'a> Scanner<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            current: 0,
+            start: 0,
+            line: 1,
+            column: 1,
+            keywords: keywords(),
+        }
+    }
+
+    /// Scans the whole filter into a list of tokens ending with [`TokenType::Eof`].
+    pub(crate) fn scan(&mut self) -> Result<Vec<Token<'a>>, JqError> {
+        let mut tokens = vec![];
+
+        loop {
+            let token = self.scan_token()?;
+
+            let is_eof = token.typ == TokenType::Eof;
+
+            tokens.push(token);
+
+            if is_eof {
+                break;
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    fn scan_token(&mut self) -> Result<Token<'a>, JqError> {
+        loop {
+            if self.is_at_end() {
+                return Ok(Token {
+                    typ: TokenType::Eof,
+                    lexeme: "\0",
+                    position: self.position(),
+                    literal: None,
+                });
+            }
+
+            self.start = self.current;
+
+            let position = self.position();
+
+            let typ = match self.advance() {
+                '.' if self.peek() == '.' => {
+                    self.advance();
+
+                    TokenType::DotDot
+                }
+                '.' if is_identifier_start(self.peek()) => return Ok(self.field(position)),
+                '.' => TokenType::Dot,
+                '|' => TokenType::Pipe,
+                ',' => TokenType::Comma,
+                ':' => TokenType::Colon,
+                ';' => TokenType::Semicolon,
+                '?' => TokenType::Question,
+                '+' => TokenType::Plus,
+                '-' => TokenType::Minus,
+                '*' => TokenType::Star,
+                '%' => TokenType::Percent,
+                '(' => TokenType::LeftParen,
+                ')' => TokenType::RightParen,
+                '[' => TokenType::LeftSquareBracket,
+                ']' => TokenType::RightSquareBracket,
+                '{' => TokenType::LeftBrace,
+                '}' => TokenType::RightBrace,
+                '/' if self.mat('/') => TokenType::SlashSlash,
+                '/' => TokenType::Slash,
+                '=' if self.mat('=') => TokenType::EqualEqual,
+                '!' if self.mat('=') => TokenType::BangEqual,
+                '<' if self.mat('=') => TokenType::LessEqual,
+                '<' => TokenType::Less,
+                '>' if self.mat('=') => TokenType::GreaterEqual,
+                '>' => TokenType::Greater,
+                '=' => {
+                    return Err(JqError::syntax(
+                        "Unexpected '=', assignments aren't supported. Use '==' to compare.",
+                        position,
+                    ))
+                }
+                '!' => {
+                    return Err(JqError::syntax(
+                        "Unexpected '!', use 'not' to negate.",
+                        position,
+                    ))
+                }
+                '"' => return self.string(position),
+                // comments go to the end of the line
+                '#' => {
+                    while self.peek() != '\n' && !self.is_at_end() {
+                        self.advance();
+                    }
+
+                    continue;
+                }
+                ' ' | '\t' | '\n' | '\r' => continue,
+                c if c.is_ascii_digit() => return self.number(position),
+                c if is_identifier_start(c) => return Ok(self.identifier(position)),
+                c => {
+                    return Err(JqError::syntax(
+                        format!("Unexpected character '{}'.", c.escape_debug()),
+                        position,
+                    ))
+                }
+            };
+
+            return Ok(self.token(typ, position));
+        }
+    }
+
+    /// `.name` after the `.`
+    fn field(&mut self, position: Position) -> Token<'a> {
+        while is_identifier_part(self.peek()) {
+            self.advance();
+        }
+
+        let name = &self.source[self.start + 1..self.current];
+
+        Token {
+            literal: Some(Literal::String(name.to_string())),
+            ..self.token(TokenType::Field, position)
+        }
+    }
+
+    fn identifier(&mut self, position: Position) -> Token<'a> {
+        while is_identifier_part(self.peek()) {
+            self.advance();
+        }
+
+        let name = &self.source[self.start..self.current];
+
+        match self.keywords.get(name) {
+            Some(typ) => self.token(*typ, position),
+            None => Token {
+                literal: Some(Literal::String(name.to_string())),
+                ..self.token(TokenType::Identifier, position)
+            },
+        }
+    }
+
+    fn number(&mut self, position: Position) -> Result<Token<'a>, JqError> {
+        while self.peek().is_ascii_digit() {
+            self.advance();
+        }
+
+        let mut float = false;
+
+        // `1.foo` isn't a fraction
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            self.advance();
+
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+
+            float = true;
+        }
+
+        if matches!(self.peek(), 'e' | 'E') {
+            self.advance();
+
+            if !self.mat('+') {
+                self.mat('-');
+            }
+
+            if !self.peek().is_ascii_digit() {
+                return Err(JqError::syntax(
+                    "Expected digit in the exponent.",
+                    self.position(),
+                ));
+            }
+
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+
+            float = true;
+        }
+
+        let lexeme = &self.source[self.start..self.current];
+
+        let number = if float {
+            Number::from_float_lexeme(lexeme)
+        } else {
+            Number::from_integer_lexeme(lexeme)
+        };
+
+        let Some(number) = number else {
+            return Err(JqError::syntax(
+                format!("Number '{}' is out of range.", lexeme),
+                position,
+            ));
+        };
+
+        Ok(Token {
+            literal: Some(Literal::Number(number)),
+            ..self.token(TokenType::Number, position)
+        })
+    }
+
+    /// Scans a string after the opening quote, decoding escape sequences and scanning
+    /// the filters of `\(...)` interpolations.
+    fn string(&mut self, position: Position) -> Result<Token<'a>, JqError> {
+        // scanning interpolations moves `start`
+        let start = self.start;
+
+        let mut parts = vec![];
+        let mut text = String::new();
+
+        loop {
+            if self.is_at_end() {
+                return Err(JqError::syntax("Unclosed '\"'.", position));
+            }
+
+            let escape = self.position();
+
+            match self.advance() {
+                '"' => break,
+                '\\' if self.mat('(') => {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                    parts.push(Part::Interpolation(self.interpolation(escape)?));
+                }
+                '\\' => self.escape(&mut text, escape)?,
+                c => text.push(c),
+            }
+        }
+
+        let literal = if parts.is_empty() {
+            Literal::String(text)
+        } else {
+            parts.push(Part::Text(text));
+
+            Literal::Template(parts)
+        };
+
+        Ok(Token {
+            typ: TokenType::String,
+            lexeme: &self.source[start..self.current],
+            position,
+            literal: Some(literal),
+        })
+    }
+
+    /// Scans the tokens of an interpolation up to the `)` closing it, which is replaced
+    /// by [`TokenType::Eof`].
+    fn interpolation(&mut self, position: Position) -> Result<Vec<Token<'a>>, JqError> {
+        let mut tokens = vec![];
+        let mut depth = 0;
+
+        loop {
+            let token = self.scan_token()?;
+
+            match token.typ {
+                TokenType::LeftParen => depth += 1,
+                TokenType::RightParen if depth == 0 => {
+                    tokens.push(Token {
+                        typ: TokenType::Eof,
+                        ..token
+                    });
+
+                    return Ok(tokens);
+                }
+                TokenType::RightParen => depth -= 1,
+                TokenType::Eof => {
+                    return Err(JqError::syntax("Unclosed '\\('.", position));
+                }
+                _ => {}
+            }
+
+            tokens.push(token);
+        }
+    }
+
+    /// Decodes the escape sequence after a `\` onto `text`.
+    fn escape(&mut self, text: &mut String, position: Position) -> Result<(), JqError> {
+        if self.is_at_end() {
+            return Err(JqError::syntax("Unterminated escape sequence.", position));
+        }
+
+        match self.advance() {
+            '"' => text.push('"'),
+            '\\' => text.push('\\'),
+            '/' => text.push('/'),
+            'b' => text.push('\u{8}'),
+            'f' => text.push('\u{c}'),
+            'n' => text.push('\n'),
+            'r' => text.push('\r'),
+            't' => text.push('\t'),
+            'u' => {
+                let unit = self.hex_escape(position)?;
+
+                let code_point = match unit {
+                    0xD800..=0xDBFF => {
+                        // a high surrogate has to be followed by an escaped low surrogate
+                        if self.peek() != '\\' || self.peek_next() != 'u' {
+                            return Err(JqError::syntax(
+                                format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                                position,
+                            ));
+                        }
+
+                        self.advance();
+                        self.advance();
+
+                        let low = self.hex_escape(position)?;
+
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(JqError::syntax(
+                                format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                                position,
+                            ));
+                        }
+
+                        0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => {
+                        return Err(JqError::syntax(
+                            format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                            position,
+                        ));
+                    }
+                    _ => unit,
+                };
+
+                // surrogates have been handled above so this is always a valid scalar value
+                text.push(char::from_u32(code_point).unwrap());
+            }
+            c => {
+                return Err(JqError::syntax(
+                    format!("Invalid escape sequence '\\{}'.", c.escape_debug()),
+                    position,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the 4 hex digits of a `\uXXXX` escape returning the UTF-16 code unit.
+    fn hex_escape(&mut self, position: Position) -> Result<u32, JqError> {
+        let mut unit = 0;
+
+        for _ in 0..4 {
+            let Some(digit) = self.peek().to_digit(16) else {
+                return Err(JqError::syntax(
+                    "Expected 4 hex digits after '\\u'.",
+                    position,
+                ));
+            };
+
+            self.advance();
+
+            unit = unit * 16 + digit;
+        }
+
+        Ok(unit)
+    }
+
+    fn token(&self, typ: TokenType, position: Position) -> Token<'a> {
+        Token {
+            typ,
+            lexeme: &self.source[self.start..self.current],
+            position,
+            literal: None,
+        }
+    }
+
+    /// Consumes `c` if it is next.
+    fn mat(&mut self, c: char) -> bool {
+        if self.peek() == c && !self.is_at_end() {
+            self.advance();
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn advance(&mut self) -> char {
+        let c = self.peek();
+
+        self.current += c.len_utf8();
+
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        c
+    }
+
+    /// The current char or `'\0'` at the end.
+    fn peek(&self) -> char {
+        self.source[self.current..].chars().next().unwrap_or('\0')
+    }
+
+    fn peek_next(&self) -> char {
+        let mut chars = self.source[self.current..].chars();
+
+        chars.next();
+        chars.next().unwrap_or('\0')
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+
+    /// Columns are counted in chars.
+    fn position(&self) -> Position {
+        Position::new(self.current, self.line, self.column)
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_identifier_part(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crate::parser::{number::Number, position::Position};
+
+#[derive(Debug, Clone)]
+pub(crate) struct Token<'a> {
+    pub(crate) typ: TokenType,
+    /// The token exactly as it appears in the program
+    pub(crate) lexeme: &'a str,
+    /// Where the token starts in the program
+    pub(crate) position: Position,
+    pub(crate) literal: Option<Literal<'a>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TokenType {
+    // Single character tokens
+    Dot,
+    Pipe,
+    Comma,
+    Colon,
+    Semicolon,
+    Question,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Less,
+    Greater,
+    LeftParen,
+    RightParen,
+    LeftSquareBracket,
+    RightSquareBracket,
+    LeftBrace,
+    RightBrace,
+
+    // Two character tokens
+    DotDot,
+    SlashSlash,
+    EqualEqual,
+    BangEqual,
+    LessEqual,
+    GreaterEqual,
+
+    // Literals
+    /// `.name`
+    Field,
+    Number,
+    String,
+    Identifier,
+
+    // Keywords
+    And,
+    Or,
+    If,
+    Then,
+    Elif,
+    Else,
+    End,
+    True,
+    False,
+    Null,
+
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Literal<'a> {
+    /// The name of a field or identifier, or a string without interpolations
+    String(String),
+    Number(Number),
+    /// A string with `\(...)` in it
+    Template(Vec<Part<'a>>),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Part<'a> {
+    Text(String),
+    /// The tokens of the interpolated filter ending with [`TokenType::Eof`]
+    Interpolation(Vec<Token<'a>>),
+}
+
+pub(crate) fn keywords() -> HashMap<&'static str, TokenType> {
+    let mut map: HashMap<&str, TokenType> = HashMap::new();
+
+    map.insert("and", TokenType::And);
+    map.insert("or", TokenType::Or);
+    map.insert("if", TokenType::If);
+    map.insert("then", TokenType::Then);
+    map.insert("elif", TokenType::Elif);
+    map.insert("else", TokenType::Else);
+    map.insert("end", TokenType::End);
+    map.insert("true", TokenType::True);
+    map.insert("false", TokenType::False);
+    map.insert("null", TokenType::Null);
+
+    map
+}
@@ -1 +1,25 @@
-pub mod parser;
\ No newline at end of file
+mod macros;
+
+pub mod parser;
+
+pub mod serializer;
+
+pub mod lines;
+
+pub mod writer;
+
+pub mod convert;
+
+pub mod pointer;
+
+pub mod path;
+
+pub mod jq;
+
+pub mod patch;
+
+#[cfg(feature = "serde")]
+pub mod de;
+
+#[cfg(feature = "serde")]
+pub mod ser;
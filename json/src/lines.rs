@@ -0,0 +1,216 @@
+//! Reading and writing [JSON Lines](https://jsonlines.org) / NDJSON where every line holds one document.
+
+use std::io::{BufRead, Write};
+
+use crate::{
+    parser::{
+        documents::Document,
+        error::{Error, Warning},
+        position::Position,
+        Options, Parser, Value,
+    },
+    serializer,
+};
+
+/// Options for how a [`LinesReader`] treats lines that aren't records.
+#[derive(Debug, Clone)]
+pub struct LinesOptions {
+    /// Skips lines that only contain whitespace
+    pub skip_blank_lines: bool,
+    /// Skips lines starting with `//` or `#`
+    pub skip_comment_lines: bool,
+    /// Collects records that fail to parse into [`LinesReader::errors`] and continues
+    /// with the next line instead of stopping at the first error
+    pub keep_going: bool,
+}
+
+impl Default for LinesOptions {
+    /// ```text
+    /// {
+    ///   skip_blank_lines: true,
+    ///   skip_comment_lines: false,
+    ///   keep_going: false,
+    /// }
+    /// ```
+    fn default() -> Self {
+        Self {
+            skip_blank_lines: true,
+            skip_comment_lines: false,
+            keep_going: false,
+        }
+    }
+}
+
+/// Reads one document per line from a [`BufRead`].
+///
+/// Every document is returned with the position of the physical line it was on and
+/// errors are relocated to the line they occurred on.
+pub struct LinesReader<R: BufRead> {
+    reader: R,
+    parser_options: Options,
+    options: LinesOptions,
+    line: usize,
+    offset: usize,
+    buf: Vec<u8>,
+    errors: Vec<Error>,
+    warnings: Vec<Warning>,
+    done: bool,
+}
+
+impl<R: BufRead> LinesReader<R> {
+    pub fn new(reader: R, parser_options: Options, options: LinesOptions) -> Self {
+        Self {
+            reader,
+            parser_options,
+            options,
+            line: 0,
+            offset: 0,
+            buf: vec![],
+            errors: vec![],
+            warnings: vec![],
+            done: false,
+        }
+    }
+
+    /// Records that failed to parse while in `keep_going` mode.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// Takes the records that failed to parse while in `keep_going` mode.
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Warnings of every record read so far, positioned on the line they occurred on.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Takes the warnings of the records read so far.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next record returning `None` at the end of the stream.
+    fn read_record(&mut self) -> Option<Result<Document<'static>, Error>> {
+        loop {
+            self.buf.clear();
+
+            let read = match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(read) => read,
+                Err(err) => return Some(Err(err.into())),
+            };
+
+            if read == 0 {
+                return None;
+            }
+
+            self.line += 1;
+
+            let start = Position::new(self.offset, self.line, 1);
+
+            self.offset += read;
+
+            // a line that isn't UTF-8 is a bad record, not a broken stream
+            let Ok(line) = std::str::from_utf8(&self.buf) else {
+                return Some(Err(Error::syntax("Line is not valid UTF-8.", start)));
+            };
+
+            let line = line
+                .strip_suffix('\n')
+                .map(|line| line.strip_suffix('\r').unwrap_or(line))
+                .unwrap_or(line);
+
+            let trimmed = line.trim_start();
+
+            if self.options.skip_blank_lines && trimmed.is_empty() {
+                continue;
+            }
+
+            if self.options.skip_comment_lines
+                && (trimmed.starts_with("//") || trimmed.starts_with('#'))
+            {
+                continue;
+            }
+
+            let mut parser = Parser::new(self.parser_options.clone());
+
+            // the line buffer is reused so the value can't borrow from it
+            let result = parser
+                .parse(line)
+                .map(|value| Document {
+                    value: value.into_owned(),
+                    position: start,
+                })
+                .map_err(|err| err.relocate(start));
+
+            self.warnings.extend(
+                parser
+                    .warnings
+                    .into_iter()
+                    .map(|warning| warning.relocate(start)),
+            );
+
+            return Some(result);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LinesReader<R> {
+    type Item = Result<Document<'static>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.read_record() {
+                Some(Ok(document)) => return Some(Ok(document)),
+                // io errors can't be recovered from
+                Some(Err(err)) if self.options.keep_going && !matches!(err, Error::Io(_)) => {
+                    self.errors.push(err);
+                }
+                Some(Err(err)) => {
+                    self.done = true;
+
+                    return Some(Err(err));
+                }
+                None => self.done = true,
+            }
+        }
+
+        None
+    }
+}
+
+/// Writes one compact document per line.
+pub struct LinesWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> LinesWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes `value` followed by a line feed.
+    pub fn write(&mut self, value: &Value) -> Result<(), Error> {
+        serializer::to_writer(&mut self.writer, value)?;
+
+        self.writer.write_all(b"\n")?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
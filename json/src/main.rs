@@ -7,9 +7,9 @@ fn main() -> Result<(), Error> {
 
     let mut parser: Parser = Parser::new(Options::js());
 
-    let result: Value =  parser.parse(source)?;
+    let result: Value = parser.parse(&source)?;
 
-    dbg!(result);
+    println!("{:#}", result);
 
     Ok(())
 }
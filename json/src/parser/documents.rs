@@ -0,0 +1,265 @@
+use std::borrow::Cow;
+
+use super::{
+    error::Error,
+    position::Position,
+    scanner::Scanner,
+    tokens::{Token, TokenType},
+    Parser, Value,
+};
+
+/// How the documents given to [`Parser::documents`] are separated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sequence {
+    /// Documents directly follow each other, optionally separated by whitespace.
+    ///
+    /// ```text
+    /// {"a": 1} {"a": 2}
+    /// [1][2]
+    /// ```
+    #[default]
+    Concatenated,
+    /// RFC 7464 JSON text sequences where every document is preceded by a record
+    /// separator (U+001E) and followed by a line feed.
+    ///
+    /// A document that fails to parse, including one with an invalid token, is reported
+    /// and parsing continues at the next record separator.
+    RecordSeparated,
+}
+
+/// A single document of a multi-document source.
+#[derive(Debug, Clone)]
+pub struct Document<'a> {
+    pub value: Value<'a>,
+    /// Where the document starts in the source
+    pub position: Position,
+}
+
+/// Iterator over the documents of a source created by [`Parser::documents`].
+pub struct Documents<'p, 'a> {
+    parser: &'p mut Parser<'a>,
+    sequence: Sequence,
+    done: bool,
+    /// Scans a record separated source one record at a time, unused for concatenated ones
+    scanner: Scanner<'a>,
+    /// The separator or end of source that ended the last record
+    lookahead: Option<Token<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    /// Parses every document in `source` into a list.
+    pub fn parse_all(
+        &mut self,
+        source: &'a str,
+        sequence: Sequence,
+    ) -> Result<Vec<Document<'a>>, Error> {
+        self.documents(source, sequence)?.collect()
+    }
+
+    /// Returns an iterator over the documents in `source`.
+    ///
+    /// A concatenated source is scanned up front so an invalid token anywhere in it is
+    /// returned as an error here. A record separated source is scanned a record at a
+    /// time and an invalid token is returned as the error of its record.
+    pub fn documents(
+        &mut self,
+        source: &'a str,
+        sequence: Sequence,
+    ) -> Result<Documents<'_, 'a>, Error> {
+        match sequence {
+            Sequence::Concatenated => self.scan(source)?,
+            Sequence::RecordSeparated => {
+                self.source = source;
+                self.tokens = vec![];
+                self.current = 0;
+                self.warnings.clear();
+            }
+        }
+
+        let scanner = Scanner::from_source(source, &self.options);
+
+        Ok(Documents {
+            parser: self,
+            sequence,
+            done: false,
+            scanner,
+            lookahead: None,
+        })
+    }
+}
+
+impl<'a> Iterator for Documents<'_, 'a> {
+    type Item = Result<Document<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = match self.sequence {
+            Sequence::Concatenated => self.concatenated(),
+            Sequence::RecordSeparated => self.record_separated(),
+        };
+
+        match result {
+            Some(Ok(document)) => Some(Ok(document)),
+            Some(Err(err)) => {
+                // there is no telling where the next document starts in a concatenated
+                // source, a record separated one continues with the next record
+                if self.sequence == Sequence::Concatenated {
+                    self.done = true;
+                }
+
+                Some(Err(err))
+            }
+            None => {
+                self.done = true;
+
+                None
+            }
+        }
+    }
+}
+
+impl<'a> Documents<'_, 'a> {
+    fn concatenated(&mut self) -> Option<Result<Document<'a>, Error>> {
+        if self.parser.is_at_end() {
+            return None;
+        }
+
+        let position = self.parser.peek().position;
+
+        Some(
+            self.parser
+                .value()
+                .map(|value| Document { value, position }),
+        )
+    }
+
+    fn record_separated(&mut self) -> Option<Result<Document<'a>, Error>> {
+        // empty records are skipped
+        loop {
+            let separator = match self.next_token() {
+                Ok(token) => token,
+                Err(err) => {
+                    self.scanner.skip_record();
+
+                    return Some(Err(err));
+                }
+            };
+
+            match separator.typ {
+                TokenType::Eof => return None,
+                TokenType::RecordSeparator => {}
+                _ => {
+                    self.scanner.skip_record();
+
+                    return Some(Err(Error::syntax(
+                        "Expected record separator before JSON text.",
+                        separator.position,
+                    )));
+                }
+            }
+
+            match self.scan_record() {
+                Ok(true) => break,
+                Ok(false) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        let first = self.parser.peek();
+
+        let (first_typ, first_position, first_end) = (
+            first.typ,
+            first.position,
+            first.position.offset + first.lexeme.len(),
+        );
+
+        let value = match self.parser.value() {
+            Ok(value) => value,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let next = self.parser.peek();
+
+        if next.typ != TokenType::RecordSeparator && next.typ != TokenType::Eof {
+            return Some(Err(Error::syntax(
+                "Expected record separator after JSON text.",
+                next.position,
+            )));
+        }
+
+        // a top level number, true, false or null directly followed by the next record
+        // may have been cut off so it can't be trusted (RFC 7464 section 2.4)
+        let is_literal = matches!(
+            first_typ,
+            TokenType::Number | TokenType::True | TokenType::False | TokenType::Null
+        );
+
+        if is_literal && next.position.offset == first_end {
+            return Some(Err(Error::syntax(
+                "JSON text may have been truncated.",
+                first_position,
+            )));
+        }
+
+        Some(Ok(Document {
+            value,
+            position: first_position,
+        }))
+    }
+
+    /// Scans the tokens up to the next record separator into the parser, ending them
+    /// with [`TokenType::Eof`] in place of the separator. Returns `false` for an empty
+    /// record.
+    ///
+    /// After an invalid token the rest of the record is skipped.
+    fn scan_record(&mut self) -> Result<bool, Error> {
+        let mut tokens = vec![];
+
+        loop {
+            let token = match self.next_token() {
+                Ok(token) => token,
+                Err(err) => {
+                    self.scanner.skip_record();
+
+                    return Err(err);
+                }
+            };
+
+            if token.typ != TokenType::RecordSeparator && token.typ != TokenType::Eof {
+                tokens.push(token);
+
+                continue;
+            }
+
+            let end = Token {
+                typ: TokenType::Eof,
+                position: token.position,
+                lexeme: Cow::Borrowed("\0"),
+                literal: None,
+            };
+
+            self.lookahead = Some(token);
+
+            if tokens.is_empty() {
+                return Ok(false);
+            }
+
+            tokens.push(end);
+
+            self.parser.tokens = tokens;
+            self.parser.current = 0;
+
+            return Ok(true);
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token<'a>, Error> {
+        match self.lookahead.take() {
+            Some(token) => Ok(token),
+            None => self.scanner.next_token(),
+        }
+    }
+}
@@ -1,8 +1,100 @@
+use std::io;
+
 use thiserror::Error;
 
+use super::position::Position;
+
 #[derive(Debug, Error)]
 pub enum Error {
     /// Error occurs during the parsing step
-    #[error("Syntax Error: {0}")]
-    SyntaxError(String),
+    #[error("Syntax Error: {position}: {message}")]
+    SyntaxError { message: String, position: Position },
+    /// A key appeared twice in an object with [`super::DuplicateKeys::Error`]
+    #[error("Syntax Error: {position}: Duplicate key '{key}' found in object, first defined at {first}.")]
+    DuplicateKey {
+        key: String,
+        /// Where the repeated key is
+        position: Position,
+        /// Where the key was first defined
+        first: Position,
+    },
+    /// Error occurs reading or writing the underlying stream
+    #[error("IO Error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl Error {
+    pub fn syntax(message: impl Into<String>, position: Position) -> Self {
+        Self::SyntaxError {
+            message: message.into(),
+            position,
+        }
+    }
+
+    /// Moves the position of the error for a piece of a larger source starting at `start`.
+    pub(crate) fn relocate(self, start: Position) -> Self {
+        match self {
+            Self::SyntaxError { message, position } => Self::SyntaxError {
+                message,
+                position: position.relocate(start),
+            },
+            Self::DuplicateKey {
+                key,
+                position,
+                first,
+            } => Self::DuplicateKey {
+                key,
+                position: position.relocate(start),
+                first: first.relocate(start),
+            },
+            Self::Io(err) => Self::Io(err),
+        }
+    }
+}
+
+/// Something that didn't stop the parser but may not be what was intended.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Warning {
+    /// A key appeared twice in an object and was handled by the
+    /// [`super::DuplicateKeys`] policy
+    #[error(
+        "Warning: {position}: Duplicate key '{key}' found in object, first defined at {first}."
+    )]
+    DuplicateKey {
+        key: String,
+        /// Where the repeated key is
+        position: Position,
+        /// Where the key was first defined
+        first: Position,
+    },
+}
+
+impl Warning {
+    /// Moves the position of the warning for a piece of a larger source starting at `start`.
+    pub(crate) fn relocate(self, start: Position) -> Self {
+        match self {
+            Self::DuplicateKey {
+                key,
+                position,
+                first,
+            } => Self::DuplicateKey {
+                key,
+                position: position.relocate(start),
+                first: first.relocate(start),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum NumberError {
+    /// The number has a fraction and can't be converted into an integer
+    #[error("'{0}' is not an integer")]
+    NotAnInteger(String),
+    /// The number is too large or too small for the type
+    #[error("'{0}' is out of range for {1}")]
+    OutOfRange(String, &'static str),
+    /// The number can only be represented approximately by the type
+    #[error("'{0}' can't be represented exactly as {1}")]
+    PrecisionLoss(String, &'static str),
 }
@@ -0,0 +1,394 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{Empty, Read},
+};
+
+use super::{
+    error::{Error, Warning},
+    number::Number,
+    position::Position,
+    scanner::Scanner,
+    tokens::{Literal, Token, TokenType},
+    DuplicateKeys, Options,
+};
+
+/// A single step through a document.
+#[derive(Debug, Clone, PartialEq)]
+///
+/// Keys and strings borrow from the source unless they contained escape sequences
+/// or the source is read with [`EventParser::from_reader`].
+pub enum EventKind<'a> {
+    StartObject,
+    /// The key of the property whose value follows
+    Key(Cow<'a, str>),
+    EndObject,
+    StartArray,
+    EndArray,
+    String(Cow<'a, str>),
+    Number(Number),
+    Bool(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event<'a> {
+    pub kind: EventKind<'a>,
+    /// Where the token that caused the event starts
+    pub position: Position,
+}
+
+/// What the innermost open container expects next.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    ArrayStart,
+    /// A value was just read
+    ArrayValue,
+    /// A comma was just read at the position
+    ArrayComma(Position),
+    ObjectStart,
+    /// A key and its colon were just read
+    ObjectColon,
+    /// A value was just read
+    ObjectValue,
+    /// A comma was just read at the position
+    ObjectComma(Position),
+}
+
+/// A pull parser that produces [`Event`]s instead of building a [`super::Value`].
+///
+/// Only a stack of the open containers is kept, along with the keys of the open
+/// objects, so memory grows with the depth and width of the objects being read rather
+/// than the size of the document. With [`DuplicateKeys::Ignore`] the keys aren't kept
+/// either and memory only grows with the depth. Combined with
+/// [`EventParser::from_reader`] this can process documents that don't fit into memory.
+///
+/// Duplicate keys follow [`Options::duplicate_keys`]: [`DuplicateKeys::Error`] fails
+/// and every other policy but [`DuplicateKeys::Ignore`] adds a [`Warning`]. The
+/// [`EventKind::Key`] event of the duplicate is still produced, which value to keep is
+/// up to the consumer.
+pub struct EventParser<'a, R = Empty> {
+    scanner: Scanner<'a, R>,
+    options: Options,
+    stack: Vec<State>,
+    /// The keys read so far of every open object and where they were first defined,
+    /// empty with [`DuplicateKeys::Ignore`]
+    keys: Vec<HashMap<String, Position>>,
+    warnings: Vec<Warning>,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> EventParser<'a> {
+    pub fn new(source: &'a str, options: &Options) -> Self {
+        Self {
+            scanner: Scanner::from_source(source, options),
+            options: options.clone(),
+            stack: vec![],
+            keys: vec![],
+            warnings: vec![],
+            started: false,
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> EventParser<'static, R> {
+    /// Creates an event parser that pulls its tokens from `reader` in chunks.
+    pub fn from_reader(reader: R, options: &Options) -> Self {
+        Self {
+            scanner: Scanner::from_reader(reader, options),
+            options: options.clone(),
+            stack: vec![],
+            keys: vec![],
+            warnings: vec![],
+            started: false,
+            done: false,
+        }
+    }
+}
+
+impl<'a, R: Read> EventParser<'a, R> {
+    /// How many containers are currently open.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Duplicate keys found so far with a policy other than [`DuplicateKeys::Error`] or
+    /// [`DuplicateKeys::Ignore`].
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Takes the warnings found so far.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Returns the next event or `None` once the document has ended.
+    pub fn next_event(&mut self) -> Result<Option<Event<'a>>, Error> {
+        loop {
+            let token = self.scanner.next_token()?;
+
+            let state = match self.stack.last() {
+                Some(state) => *state,
+                None => {
+                    if !self.started {
+                        self.started = true;
+
+                        return self.value(token).map(Some);
+                    }
+
+                    if token.typ == TokenType::Eof {
+                        return Ok(None);
+                    }
+
+                    return Err(Error::syntax(
+                        format!(
+                            "Unexpected '{}' after the top level value.",
+                            token.lexeme.escape_debug()
+                        ),
+                        token.position,
+                    ));
+                }
+            };
+
+            match state {
+                State::ArrayStart => {
+                    if token.typ == TokenType::RightSquareBracket {
+                        return Ok(Some(self.end(TokenType::RightSquareBracket, token)));
+                    }
+
+                    self.set_state(State::ArrayValue);
+
+                    return self.value(token).map(Some);
+                }
+                State::ArrayValue => match token.typ {
+                    TokenType::Comma => self.set_state(State::ArrayComma(token.position)),
+                    TokenType::RightSquareBracket => {
+                        return Ok(Some(self.end(TokenType::RightSquareBracket, token)));
+                    }
+                    TokenType::Eof => {
+                        return Err(Error::syntax(
+                            "Expected ']' at the end of an array.",
+                            token.position,
+                        ));
+                    }
+                    _ => {
+                        return Err(Error::syntax(
+                            "Expected ',' before next value in array.",
+                            token.position,
+                        ));
+                    }
+                },
+                State::ArrayComma(comma) => {
+                    if token.typ == TokenType::RightSquareBracket {
+                        self.trailing_comma(comma)?;
+
+                        return Ok(Some(self.end(TokenType::RightSquareBracket, token)));
+                    }
+
+                    self.set_state(State::ArrayValue);
+
+                    return self.value(token).map(Some);
+                }
+                State::ObjectStart => {
+                    if token.typ == TokenType::RightBrace {
+                        return Ok(Some(self.end(TokenType::RightBrace, token)));
+                    }
+
+                    return self.key(token).map(Some);
+                }
+                State::ObjectColon => {
+                    self.set_state(State::ObjectValue);
+
+                    return self.value(token).map(Some);
+                }
+                State::ObjectValue => match token.typ {
+                    TokenType::Comma => self.set_state(State::ObjectComma(token.position)),
+                    TokenType::RightBrace => {
+                        return Ok(Some(self.end(TokenType::RightBrace, token)));
+                    }
+                    TokenType::Eof => {
+                        return Err(Error::syntax(
+                            "Expected '}' at the end of an object.",
+                            token.position,
+                        ));
+                    }
+                    _ => {
+                        return Err(Error::syntax(
+                            "Expected ',' before next property in object.",
+                            token.position,
+                        ));
+                    }
+                },
+                State::ObjectComma(comma) => {
+                    if token.typ == TokenType::RightBrace {
+                        self.trailing_comma(comma)?;
+
+                        return Ok(Some(self.end(TokenType::RightBrace, token)));
+                    }
+
+                    return self.key(token).map(Some);
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, token: Token<'a>) -> Result<Event<'a>, Error> {
+        let kind = match token.typ {
+            TokenType::LeftBrace => {
+                self.stack.push(State::ObjectStart);
+
+                if self.options.duplicate_keys != DuplicateKeys::Ignore {
+                    self.keys.push(HashMap::new());
+                }
+
+                EventKind::StartObject
+            }
+            TokenType::LeftSquareBracket => {
+                self.stack.push(State::ArrayStart);
+
+                EventKind::StartArray
+            }
+            TokenType::True => EventKind::Bool(true),
+            TokenType::False => EventKind::Bool(false),
+            TokenType::Null => EventKind::Null,
+            TokenType::String | TokenType::Number => match token.literal {
+                Some(Literal::String(str)) => EventKind::String(str),
+                Some(Literal::Number(num)) => EventKind::Number(num),
+                _ => return Err(Error::syntax("Expected value.", token.position)),
+            },
+            _ => return Err(Error::syntax("Expected value.", token.position)),
+        };
+
+        Ok(Event {
+            kind,
+            position: token.position,
+        })
+    }
+
+    /// Reads a key along with the colon after it.
+    fn key(&mut self, token: Token<'a>) -> Result<Event<'a>, Error> {
+        if token.typ == TokenType::Eof {
+            return Err(Error::syntax(
+                "Expected '}' at the end of an object.",
+                token.position,
+            ));
+        }
+
+        // identifiers will not make it past the scanner if not configured to take them
+        let key = match (token.typ, token.literal) {
+            (TokenType::String | TokenType::Identifier, Some(Literal::String(str))) => str,
+            _ => {
+                return Err(Error::syntax(
+                    "Expected key for key value pair.",
+                    token.position,
+                ));
+            }
+        };
+
+        let colon = self.scanner.next_token()?;
+
+        if colon.typ != TokenType::Colon {
+            return Err(Error::syntax(
+                "Expected ':' after identifier.",
+                colon.position,
+            ));
+        }
+
+        self.duplicate_key(&key, token.position)?;
+
+        self.set_state(State::ObjectColon);
+
+        Ok(Event {
+            kind: EventKind::Key(key),
+            position: token.position,
+        })
+    }
+
+    fn end(&mut self, typ: TokenType, token: Token<'a>) -> Event<'a> {
+        self.stack.pop();
+
+        let kind = if typ == TokenType::RightBrace {
+            self.keys.pop();
+
+            EventKind::EndObject
+        } else {
+            EventKind::EndArray
+        };
+
+        Event {
+            kind,
+            position: token.position,
+        }
+    }
+
+    /// Remembers the key in the innermost object, handling it according to
+    /// [`Options::duplicate_keys`] when it was already there.
+    fn duplicate_key(&mut self, key: &str, position: Position) -> Result<(), Error> {
+        let Some(keys) = self.keys.last_mut() else {
+            return Ok(());
+        };
+
+        let Some(&first) = keys.get(key) else {
+            keys.insert(key.to_string(), position);
+
+            return Ok(());
+        };
+
+        if self.options.duplicate_keys == DuplicateKeys::Error {
+            return Err(Error::DuplicateKey {
+                key: key.to_string(),
+                position,
+                first,
+            });
+        }
+
+        self.warnings.push(Warning::DuplicateKey {
+            key: key.to_string(),
+            position,
+            first,
+        });
+
+        Ok(())
+    }
+
+    fn trailing_comma(&self, comma: Position) -> Result<(), Error> {
+        if self.options.allow_trailing_comma {
+            return Ok(());
+        }
+
+        Err(Error::syntax("Trailing commas are not allowed.", comma))
+    }
+
+    fn set_state(&mut self, state: State) {
+        if let Some(top) = self.stack.last_mut() {
+            *top = state;
+        }
+    }
+}
+
+impl<'a, R: Read> Iterator for EventParser<'a, R> {
+    type Item = Result<Event<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_event() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.done = true;
+
+                None
+            }
+            Err(err) => {
+                self.done = true;
+
+                Some(Err(err))
+            }
+        }
+    }
+}
@@ -0,0 +1,419 @@
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    ops::{Index, IndexMut},
+    vec,
+};
+
+/// The order the properties of a [`Map`] are kept in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyOrder {
+    /// Properties stay in the order they were inserted, for objects that is the order
+    /// they appear in the source. Reading and writing a document keeps it as it was.
+    #[default]
+    Insertion,
+    /// Properties are sorted by key.
+    Sorted,
+}
+
+/// A map that keeps its properties in a configurable [`KeyOrder`].
+///
+/// Entries are stored in a `Vec` in the map's order. With [`KeyOrder::Insertion`]
+/// lookups go through a hash index of the keys, with [`KeyOrder::Sorted`] they are a
+/// binary search. Either way the API is the same.
+///
+/// [`Map::append`] allows a key to be in the map more than once, lookups by key then
+/// go to the first entry while [`Map::get_all`] returns every value of the key.
+#[derive(Clone)]
+pub struct Map<K, V> {
+    entries: Vec<(K, V)>,
+    order: KeyOrder,
+    /// Position of the first entry of every key in `entries`, only kept for [`KeyOrder::Insertion`]
+    index: HashMap<K, usize>,
+    /// Whether [`Map::append`] has ever added a key that was already there
+    duplicates: bool,
+}
+
+impl<K, V> Map<K, V> {
+    /// Creates an empty map that keeps insertion order.
+    pub fn new() -> Self {
+        Self::with_order(KeyOrder::Insertion)
+    }
+
+    pub fn with_order(order: KeyOrder) -> Self {
+        Self {
+            entries: vec![],
+            order,
+            index: HashMap::new(),
+            duplicates: false,
+        }
+    }
+
+    pub fn order(&self) -> KeyOrder {
+        self.order
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entry at `index` in the map's order.
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries.get(index).map(|(key, value)| (key, value))
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            entries: self.entries.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            entries: self.entries.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+        self.duplicates = false;
+    }
+}
+
+impl<K: Hash + Ord + Clone, V> Map<K, V> {
+    /// Inserts a property returning the previous value of `key`.
+    ///
+    /// A key that is already present keeps its place, only the value is replaced.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_full(key, value).1
+    }
+
+    /// Inserts a property returning where it is in the map along with the previous value of `key`.
+    pub fn insert_full(&mut self, key: K, value: V) -> (usize, Option<V>) {
+        match self.find(&key) {
+            Ok(i) => (i, Some(std::mem::replace(&mut self.entries[i].1, value))),
+            Err(i) => {
+                if self.order == KeyOrder::Insertion {
+                    self.index.insert(key.clone(), i);
+                }
+
+                self.entries.insert(i, (key, value));
+
+                (i, None)
+            }
+        }
+    }
+
+    /// Adds a property even if `key` is already in the map returning where it was placed.
+    ///
+    /// With [`KeyOrder::Sorted`] it goes after the entries already there for `key`.
+    pub fn append(&mut self, key: K, value: V) -> usize {
+        if self.find(&key).is_ok() {
+            self.duplicates = true;
+        }
+
+        let i = match self.order {
+            KeyOrder::Insertion => {
+                if !self.index.contains_key(&key) {
+                    self.index.insert(key.clone(), self.entries.len());
+                }
+
+                self.entries.len()
+            }
+            KeyOrder::Sorted => self.entries.partition_point(|(probe, _)| *probe <= key),
+        };
+
+        self.entries.insert(i, (key, value));
+
+        i
+    }
+
+    /// Returns every value of `key` in the map's order.
+    pub fn get_all<'m, Q>(&'m self, key: &'m Q) -> impl Iterator<Item = &'m V> + 'm
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        let tail = match self.find(key) {
+            Ok(i) => &self.entries[i..],
+            Err(_) => &[],
+        };
+
+        // without duplicates only the first entry can match
+        let tail = if self.duplicates {
+            tail
+        } else {
+            &tail[..tail.len().min(1)]
+        };
+
+        tail.iter()
+            .filter(move |(probe, _)| probe.borrow() == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the position of the first entry of `key`.
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        self.find(key).ok()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        self.find(key).ok().map(|i| &self.entries[i].1)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        self.find(key).ok().map(|i| &mut self.entries[i].1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        self.find(key).is_ok()
+    }
+
+    /// Removes the first entry of `key` returning its value. The properties after it keep their order.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes the first entry of `key` returning it along with its value.
+    /// The properties after it keep their order.
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        let i = self.find(key).ok()?;
+
+        let entry = self.entries.remove(i);
+
+        if self.order == KeyOrder::Insertion {
+            for position in self.index.values_mut() {
+                if *position > i {
+                    *position -= 1;
+                }
+            }
+
+            // the key may have been appended more than once
+            match self.entries[i..]
+                .iter()
+                .position(|(probe, _)| probe.borrow() == key)
+            {
+                Some(next) => {
+                    if let Some(position) = self.index.get_mut(key) {
+                        *position = i + next;
+                    }
+                }
+                None => {
+                    self.index.remove(key);
+                }
+            }
+        }
+
+        Some(entry)
+    }
+
+    /// Returns the position of the first entry of `key` or where it would have to be inserted.
+    fn find<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Ord + ?Sized,
+    {
+        match self.order {
+            KeyOrder::Insertion => self.index.get(key).copied().ok_or(self.entries.len()),
+            KeyOrder::Sorted => {
+                let i = self
+                    .entries
+                    .partition_point(|(probe, _)| probe.borrow() < key);
+
+                match self.entries.get(i) {
+                    Some((probe, _)) if probe.borrow() == key => Ok(i),
+                    _ => Err(i),
+                }
+            }
+        }
+    }
+}
+
+impl<K, V> Default for Map<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maps are equal when they have the same keys with the same values no matter their order.
+/// A key that is there more than once has to have the same values in the same order.
+impl<K: Hash + Ord + Clone, V: PartialEq> PartialEq for Map<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self.iter().enumerate().all(|(i, (key, value))| {
+                if !self.duplicates && !other.duplicates {
+                    return other.get(key) == Some(value);
+                }
+
+                // only compare every key once
+                self.get_index_of(key) != Some(i) || self.get_all(key).eq(other.get_all(key))
+            })
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Map<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, Q> Index<&Q> for Map<K, V>
+where
+    K: Hash + Ord + Clone + Borrow<Q>,
+    Q: Hash + Ord + ?Sized,
+{
+    type Output = V;
+
+    /// Panics if `key` isn't in the map.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in map")
+    }
+}
+
+impl<K, V, Q> IndexMut<&Q> for Map<K, V>
+where
+    K: Hash + Ord + Clone + Borrow<Q>,
+    Q: Hash + Ord + ?Sized,
+{
+    /// Panics if `key` isn't in the map.
+    fn index_mut(&mut self, key: &Q) -> &mut V {
+        self.get_mut(key).expect("key not found in map")
+    }
+}
+
+impl<K: Hash + Ord + Clone, V> FromIterator<(K, V)> for Map<K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Map::new();
+
+        map.extend(iter);
+
+        map
+    }
+}
+
+impl<K: Hash + Ord + Clone, V> Extend<(K, V)> for Map<K, V> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V> IntoIterator for Map<K, V> {
+    type Item = (K, V);
+    type IntoIter = vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'m, K, V> IntoIterator for &'m Map<K, V> {
+    type Item = (&'m K, &'m V);
+    type IntoIter = Iter<'m, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'m, K, V> IntoIterator for &'m mut Map<K, V> {
+    type Item = (&'m K, &'m mut V);
+    type IntoIter = IterMut<'m, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Iterator over the entries of a [`Map`] in its order.
+pub struct Iter<'m, K, V> {
+    entries: std::slice::Iter<'m, (K, V)>,
+}
+
+impl<'m, K, V> Iterator for Iter<'m, K, V> {
+    type Item = (&'m K, &'m V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+/// Mutable iterator over the entries of a [`Map`] in its order.
+pub struct IterMut<'m, K, V> {
+    entries: std::slice::IterMut<'m, (K, V)>,
+}
+
+impl<'m, K, V> Iterator for IterMut<'m, K, V> {
+    type Item = (&'m K, &'m mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
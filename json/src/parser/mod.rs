@@ -1,17 +1,33 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
 
-use error::Error;
+use error::{Error, Warning};
+use map::{KeyOrder, Map};
+use position::{ColumnUnit, Position};
 use scanner::Scanner;
 use tokens::{Literal, Token, TokenType};
 
 pub mod scanner;
 
+pub mod number;
+
+pub mod position;
+
 pub mod tokens;
 
 pub mod error;
 
 pub mod utils;
 
+pub mod documents;
+
+pub mod events;
+
+pub mod map;
+
+pub mod value;
+
+pub use value::Value;
+
 #[derive(Debug, Clone)]
 pub struct Options {
     /// Allows keys to not be quoted such as in JavaScript
@@ -59,6 +75,7 @@ pub struct Options {
     /// ```
     pub allow_comments: bool,
     /// If the JSON is formatted sets the width of the tab.
+    /// With [`ColumnUnit::Display`] tabs move the column to the next tab stop.
     /// This can be helpful to keep error messages in sync with the correct column.
     ///
     /// # Example
@@ -76,6 +93,57 @@ pub struct Options {
     /// }
     /// ```
     pub tab_width: usize,
+    /// The unit columns in token and error positions are counted in.
+    ///
+    /// Use [`ColumnUnit::Utf16`] for positions that line up with an LSP client or
+    /// [`ColumnUnit::Byte`] when slicing the source directly.
+    pub column_unit: ColumnUnit,
+    /// Keeps numbers exactly as they were written instead of converting them to
+    /// `u64` / `i64` / `f64`. Use [`number::Number::to_f64`], [`number::Number::to_u128`] etc.
+    /// to convert them when needed, those fail instead of silently losing precision.
+    ///
+    /// # Example
+    /// ```json
+    /// {
+    ///   "price": 0.1,
+    ///   "id": 340282366920938463463374607431768211455
+    /// }
+    /// ```
+    pub arbitrary_precision: bool,
+    /// The order the properties of parsed objects are kept in. By default this is
+    /// the order they appear in the source so a document can be written back unchanged.
+    pub key_order: KeyOrder,
+    /// What to do when a key appears more than once in the same object.
+    ///
+    /// # Example
+    /// ```json
+    /// {
+    ///   "foo": "bar",
+    ///   "foo": "baz"
+    /// }
+    /// ```
+    pub duplicate_keys: DuplicateKeys,
+}
+
+/// How the parser handles a key that appears more than once in an object.
+///
+/// Every policy except [`DuplicateKeys::Error`] and [`DuplicateKeys::Ignore`] adds a
+/// [`Warning`] to [`Parser::warnings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Fails with [`Error::DuplicateKey`] holding the positions of both keys
+    #[default]
+    Error,
+    /// Keeps the first value and ignores the ones after it
+    FirstWins,
+    /// Keeps the last value in place of the first one, as `JSON.parse` does
+    LastWins,
+    /// Keeps every value, use [`map::Map::get_all`] to read them
+    KeepAll,
+    /// Doesn't look for duplicates. Parsing keeps the last value like
+    /// [`DuplicateKeys::LastWins`] without a warning and [`events::EventParser`] doesn't
+    /// remember the keys so its memory doesn't grow with the size of an object
+    Ignore,
 }
 
 impl Options {
@@ -92,39 +160,26 @@ impl Options {
             allow_trailing_comma,
             allow_comments,
             tab_width,
-        }
-    }
-
-    /// Default strict options for parsing JSON
-    ///
-    /// ```
-    /// {
-    ///   allow_unquoted_identifier: false,
-    ///   allow_single_quotes: false,
-    ///   allow_trailing_comma: false,
-    ///   allow_comments: false,
-    ///   tab_width: 4,
-    /// }
-    /// ```
-    pub fn default() -> Self {
-        Self {
-            allow_unquoted_identifier: false,
-            allow_single_quotes: false,
-            allow_trailing_comma: false,
-            allow_comments: false,
-            tab_width: 4,
+            column_unit: ColumnUnit::Display,
+            arbitrary_precision: false,
+            key_order: KeyOrder::Insertion,
+            duplicate_keys: DuplicateKeys::Error,
         }
     }
 
     /// JavaScript style loose parsing for JSON
     ///
-    /// ```
+    /// ```text
     /// {
     ///   allow_unquoted_identifier: true,
     ///   allow_single_quotes: true,
     ///   allow_trailing_comma: true,
     ///   allow_comments: true,
     ///   tab_width: 4,
+    ///   column_unit: ColumnUnit::Display,
+    ///   arbitrary_precision: false,
+    ///   key_order: KeyOrder::Insertion,
+    ///   duplicate_keys: DuplicateKeys::LastWins,
     /// }
     /// ```
     pub fn js() -> Self {
@@ -134,66 +189,124 @@ impl Options {
             allow_trailing_comma: true,
             allow_comments: true,
             tab_width: 4,
+            column_unit: ColumnUnit::Display,
+            arbitrary_precision: false,
+            key_order: KeyOrder::Insertion,
+            duplicate_keys: DuplicateKeys::LastWins,
         }
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum Value {
-    Object(HashMap<String, Box<Value>>),
-    Array(Vec<Box<Value>>),
-    Literal(Literal),
+impl Default for Options {
+    /// Default strict options for parsing JSON
+    ///
+    /// ```text
+    /// {
+    ///   allow_unquoted_identifier: false,
+    ///   allow_single_quotes: false,
+    ///   allow_trailing_comma: false,
+    ///   allow_comments: false,
+    ///   tab_width: 4,
+    ///   column_unit: ColumnUnit::Display,
+    ///   arbitrary_precision: false,
+    ///   key_order: KeyOrder::Insertion,
+    ///   duplicate_keys: DuplicateKeys::Error,
+    /// }
+    /// ```
+    fn default() -> Self {
+        Self {
+            allow_unquoted_identifier: false,
+            allow_single_quotes: false,
+            allow_trailing_comma: false,
+            allow_comments: false,
+            tab_width: 4,
+            column_unit: ColumnUnit::Display,
+            arbitrary_precision: false,
+            key_order: KeyOrder::Insertion,
+            duplicate_keys: DuplicateKeys::Error,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct Parser {
-    pub source: String,
-    pub tokens: Vec<Token>,
+pub struct Parser<'a> {
+    pub source: &'a str,
+    pub tokens: Vec<Token<'a>>,
     pub options: Options,
     pub current: usize,
+    /// Warnings from the last call to [`Parser::parse`] or [`Parser::documents`]
+    pub warnings: Vec<Warning>,
 }
 
-impl Parser {
+impl<'a> Parser<'a> {
     pub fn new(options: Options) -> Self {
         Self {
-            source: String::new(),
+            source: "",
             tokens: vec![],
             options,
             current: 0,
+            warnings: vec![],
+        }
+    }
+
+    /// Parses a single JSON document. Anything but whitespace and comments after the
+    /// top level value is an error, use [`Parser::documents`] for multiple documents.
+    ///
+    /// Strings in the returned value borrow from `source` where possible.
+    pub fn parse(&mut self, source: &'a str) -> Result<Value<'a>, Error> {
+        self.scan(source)?;
+
+        let value = self.value()?;
+
+        if !self.is_at_end() {
+            let token = self.peek();
+
+            return Err(Error::syntax(
+                format!(
+                    "Unexpected '{}' after the top level value.",
+                    token.lexeme.escape_debug()
+                ),
+                token.position,
+            ));
         }
+
+        Ok(value)
     }
 
-    pub fn parse(&mut self, source: String) -> Result<Value, Error> {
+    fn scan(&mut self, source: &'a str) -> Result<(), Error> {
         self.source = source;
+        self.current = 0;
+        self.warnings.clear();
 
         let mut scanner = Scanner::new(&self.options);
 
-        self.tokens = scanner.scan(self.source.clone())?;
+        self.tokens = scanner.scan(source)?;
 
-        self.value()
+        Ok(())
     }
 
-    fn value(&mut self) -> Result<Value, Error> {
-        if self.mat(vec![TokenType::LeftBrace]) {
+    fn value(&mut self) -> Result<Value<'a>, Error> {
+        if self.mat(&[TokenType::LeftBrace]) {
             return self.object();
         }
 
-        if self.mat(vec![TokenType::LeftSquareBracket]) {
+        if self.mat(&[TokenType::LeftSquareBracket]) {
             return self.array();
         }
 
-        Ok(Value::Literal(self.literal()?))
+        self.literal()
     }
 
-    fn array(&mut self) -> Result<Value, Error> {
-        let mut values: Vec<Box<Value>> = vec![];
+    fn array(&mut self) -> Result<Value<'a>, Error> {
+        let mut values: Vec<Value<'a>> = vec![];
 
         let mut had_comma = false;
 
         while !self.check(TokenType::RightSquareBracket) && !self.is_at_end() {
-            if values.len() > 0 && !had_comma {
-                return Err(Error::SyntaxError(
-                    "Expected ',' before next value in array.".to_string(),
+            if !values.is_empty() && !had_comma {
+                return Err(Error::syntax(
+                    "Expected ',' before next value in array.",
+                    self.peek().position,
                 ));
             }
 
@@ -201,7 +314,7 @@ impl Parser {
 
             let value = self.value()?;
 
-            values.push(Box::new(value));
+            values.push(value);
 
             if self.check(TokenType::Comma) {
                 had_comma = true;
@@ -216,8 +329,10 @@ impl Parser {
             if self.options.allow_trailing_comma {
                 // do nothing it has already been consumed
             } else {
-                return Err(Error::SyntaxError(
-                    "Trailing commas are not allowed.".to_string(),
+                // the comma was the last thing consumed
+                return Err(Error::syntax(
+                    "Trailing commas are not allowed.",
+                    self.previous().position,
                 ));
             }
         }
@@ -230,32 +345,46 @@ impl Parser {
         Ok(Value::Array(values))
     }
 
-    fn object(&mut self) -> Result<Value, Error> {
-        let mut properties: HashMap<String, Box<Value>> = HashMap::new();
+    fn object(&mut self) -> Result<Value<'a>, Error> {
+        let mut properties: Map<Cow<'a, str>, Value<'a>> = Map::with_order(self.options.key_order);
+
+        // where every key in `properties` is, in the same order
+        let mut key_positions: Vec<Position> = vec![];
 
         let mut had_comma = false;
 
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            if properties.len() > 0 && !had_comma {
-                let last_token = self.previous();
-                return Err(Error::SyntaxError(format!(
-                    "{}:{}: Expected ',' before next property in object.",
-                    last_token.line, last_token.column
-                )));
+            if !properties.is_empty() && !had_comma {
+                return Err(Error::syntax(
+                    "Expected ',' before next property in object.",
+                    self.peek().position,
+                ));
             }
 
             had_comma = false;
 
-            let property = self.property()?;
+            let key_position = self.peek().position;
 
-            if properties.contains_key(&property.0) {
-                return Err(Error::SyntaxError(format!(
-                    "Duplicate key {} found in object.",
-                    property.0
-                )));
-            }
+            let (key, value) = self.property()?;
+
+            match properties.get_index_of(&key) {
+                Some(i) => {
+                    let first = key_positions[i];
 
-            properties.insert(property.0, Box::new(property.1));
+                    self.duplicate_key(
+                        &mut properties,
+                        &mut key_positions,
+                        (key, value),
+                        key_position,
+                        first,
+                    )?;
+                }
+                None => {
+                    let (i, _) = properties.insert_full(key, value);
+
+                    key_positions.insert(i, key_position);
+                }
+            }
 
             if self.check(TokenType::Comma) {
                 had_comma = true;
@@ -270,8 +399,10 @@ impl Parser {
             if self.options.allow_trailing_comma {
                 // do nothing its already been consumed
             } else {
-                return Err(Error::SyntaxError(
-                    "Trailing commas are not allowed.".to_string(),
+                // the comma was the last thing consumed
+                return Err(Error::syntax(
+                    "Trailing commas are not allowed.",
+                    self.previous().position,
                 ));
             }
         }
@@ -284,7 +415,54 @@ impl Parser {
         Ok(Value::Object(properties))
     }
 
-    fn property(&mut self) -> Result<(String, Value), Error> {
+    /// Handles a key that is already in `properties` according to [`Options::duplicate_keys`].
+    fn duplicate_key(
+        &mut self,
+        properties: &mut Map<Cow<'a, str>, Value<'a>>,
+        key_positions: &mut Vec<Position>,
+        (key, value): (Cow<'a, str>, Value<'a>),
+        position: Position,
+        first: Position,
+    ) -> Result<(), Error> {
+        match self.options.duplicate_keys {
+            DuplicateKeys::Error => {
+                return Err(Error::DuplicateKey {
+                    key: key.into_owned(),
+                    position,
+                    first,
+                })
+            }
+            DuplicateKeys::Ignore => {
+                properties.insert(key, value);
+
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        self.warnings.push(Warning::DuplicateKey {
+            key: key.to_string(),
+            position,
+            first,
+        });
+
+        match self.options.duplicate_keys {
+            DuplicateKeys::LastWins => {
+                properties.insert(key, value);
+            }
+            DuplicateKeys::KeepAll => {
+                let i = properties.append(key, value);
+
+                key_positions.insert(i, position);
+            }
+            // the first value is already in place
+            DuplicateKeys::FirstWins | DuplicateKeys::Error | DuplicateKeys::Ignore => {}
+        }
+
+        Ok(())
+    }
+
+    fn property(&mut self) -> Result<(Cow<'a, str>, Value<'a>), Error> {
         let identifier = self.identifier()?;
 
         self.consume(
@@ -297,72 +475,62 @@ impl Parser {
         Ok((identifier, value))
     }
 
-    fn identifier(&mut self) -> Result<String, Error> {
-        // this will not make it past the scanner if not configured to take identifiers
-        if self.mat(vec![TokenType::Identifier]) {
-            return Ok(self.previous().lexeme);
-        }
-
-        if self.mat(vec![TokenType::String]) {
-            match self.previous().literal.unwrap() {
-                Literal::String(str) => return Ok(str),
-                _ => {}
+    fn identifier(&mut self) -> Result<Cow<'a, str>, Error> {
+        // identifiers will not make it past the scanner if not configured to take them
+        if self.mat(&[TokenType::Identifier, TokenType::String]) {
+            if let Some(Literal::String(str)) = self.take_literal() {
+                return Ok(str);
             }
         }
 
-        Err(Error::SyntaxError(
-            "Expected key for key value pair.".to_string(),
+        Err(Error::syntax(
+            "Expected key for key value pair.",
+            self.peek().position,
         ))
     }
 
-    fn literal(&mut self) -> Result<Literal, Error> {
+    fn literal(&mut self) -> Result<Value<'a>, Error> {
         if self.check(TokenType::Null) {
             self.advance();
-            return Ok(Literal::Null);
+            return Ok(Value::Null);
         }
 
         if self.check(TokenType::True) {
             self.advance();
-            return Ok(Literal::True);
+            return Ok(Value::Bool(true));
         }
 
         if self.check(TokenType::False) {
             self.advance();
-            return Ok(Literal::False);
+            return Ok(Value::Bool(false));
         }
 
-        if self.check(TokenType::String) {
-            match self.peek().literal.unwrap() {
-                Literal::String(str) => {
-                    self.advance();
-                    return Ok(Literal::String(str));
-                }
+        if self.mat(&[TokenType::String, TokenType::Number]) {
+            match self.take_literal() {
+                Some(Literal::String(str)) => return Ok(Value::String(str)),
+                Some(Literal::Number(num)) => return Ok(Value::Number(num)),
                 _ => {}
             }
         }
 
-        if self.check(TokenType::Number) {
-            match self.peek().literal.unwrap() {
-                Literal::Number(num) => {
-                    self.advance();
-                    return Ok(Literal::Number(num));
-                }
-                _ => {}
-            }
-        }
+        Err(Error::syntax("Expected value.", self.peek().position))
+    }
 
-        Err(Error::SyntaxError("Expected value.".to_string()))
+    /// Moves the literal out of the token that was just consumed instead of cloning it,
+    /// every token is only ever consumed once.
+    fn take_literal(&mut self) -> Option<Literal<'a>> {
+        self.tokens[self.current - 1].literal.take()
     }
 
-    fn mat(&mut self, types: Vec<TokenType>) -> bool {
+    fn mat(&mut self, types: &[TokenType]) -> bool {
         for typ in types {
-            if self.check(typ) {
+            if self.check(*typ) {
                 self.advance();
                 return true;
             }
         }
 
-        return false;
+        false
     }
 
     fn check(&self, typ: TokenType) -> bool {
@@ -373,31 +541,31 @@ impl Parser {
         self.peek().typ == typ
     }
 
-    fn peek(&self) -> Token {
+    fn peek(&self) -> &Token<'a> {
         // this should be safe as long as it is checked before
-        self.tokens.get(self.current).unwrap().to_owned()
+        self.tokens.get(self.current).unwrap()
     }
 
-    fn previous(&self) -> Token {
+    fn previous(&self) -> &Token<'a> {
         // this should be safe as long as it is checked before
-        self.tokens.get(self.current - 1).unwrap().to_owned()
+        self.tokens.get(self.current - 1).unwrap()
     }
 
     fn is_at_end(&self) -> bool {
         self.peek().typ == TokenType::Eof
     }
 
-    fn advance(&mut self) -> Token {
-        self.current = self.current + 1;
+    fn advance(&mut self) -> &Token<'a> {
+        self.current += 1;
 
         self.previous()
     }
 
-    fn consume(&mut self, typ: TokenType, msg: String) -> Result<Token, Error> {
+    fn consume(&mut self, typ: TokenType, msg: String) -> Result<&Token<'a>, Error> {
         if self.check(typ) {
             return Ok(self.advance());
         }
 
-        Err(Error::SyntaxError(msg))
+        Err(Error::syntax(msg, self.peek().position))
     }
 }
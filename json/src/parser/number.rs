@@ -0,0 +1,393 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use super::error::NumberError;
+
+/// A JSON number.
+///
+/// Integers are kept as `u64` / `i64` so large ids don't lose precision. Anything else
+/// is kept as an `f64` along with the text it was parsed from so it can be written
+/// back exactly as it was read.
+#[derive(Debug, Clone)]
+pub struct Number {
+    n: N,
+}
+
+#[derive(Debug, Clone)]
+enum N {
+    /// Always positive or zero
+    PosInt(u64),
+    /// Always negative
+    NegInt(i64),
+    /// Always finite. The lexeme is `None` when the number was created from a Rust value.
+    Float(f64, Option<Box<str>>),
+    /// A validated number lexeme kept as is when parsing with `arbitrary_precision`
+    Decimal(Box<str>),
+}
+
+impl Number {
+    /// Creates a number from the lexeme of an integer (no fraction or exponent).
+    ///
+    /// Integers that don't fit into 64 bits fall back to an `f64`.
+    pub(crate) fn from_integer_lexeme(lexeme: &str) -> Option<Self> {
+        // -0 can't be represented as an integer
+        if lexeme == "-0" {
+            return Self::from_float_lexeme(lexeme);
+        }
+
+        if lexeme.starts_with('-') {
+            if let Ok(n) = lexeme.parse::<i64>() {
+                return Some(Self { n: N::NegInt(n) });
+            }
+        } else if let Ok(n) = lexeme.parse::<u64>() {
+            return Some(Self { n: N::PosInt(n) });
+        }
+
+        Self::from_float_lexeme(lexeme)
+    }
+
+    /// Creates a number from the lexeme of a number with a fraction and or exponent.
+    ///
+    /// Returns `None` if the number is too large to be represented by an `f64`.
+    pub(crate) fn from_float_lexeme(lexeme: &str) -> Option<Self> {
+        let f: f64 = lexeme.parse().ok()?;
+
+        if !f.is_finite() {
+            return None;
+        }
+
+        Some(Self {
+            n: N::Float(f, Some(lexeme.into())),
+        })
+    }
+
+    /// Creates a number that keeps the lexeme as is without converting it.
+    ///
+    /// The lexeme must already be validated against the number grammar.
+    pub(crate) fn from_decimal_lexeme(lexeme: &str) -> Self {
+        Self {
+            n: N::Decimal(lexeme.into()),
+        }
+    }
+
+    /// Creates a number from an `f64`. Returns `None` for `NaN` and infinities
+    /// as they can't be represented in JSON.
+    pub fn from_f64(f: f64) -> Option<Self> {
+        if !f.is_finite() {
+            return None;
+        }
+
+        Some(Self {
+            n: N::Float(f, None),
+        })
+    }
+
+    /// Returns true if the number can be represented as an `i64`.
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    /// Returns true if the number can be represented as an `u64`.
+    pub fn is_u64(&self) -> bool {
+        self.as_u64().is_some()
+    }
+
+    /// Returns true if the number is not an integer or was too large to fit into 64 bits.
+    pub fn is_f64(&self) -> bool {
+        matches!(self.n, N::Float(..))
+    }
+
+    /// Returns true if the number was kept as a decimal string by `arbitrary_precision`.
+    pub fn is_decimal(&self) -> bool {
+        matches!(self.n, N::Decimal(_))
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.n {
+            N::PosInt(n) => i64::try_from(n).ok(),
+            N::NegInt(n) => Some(n),
+            N::Float(..) => None,
+            N::Decimal(_) => self.to_i64().ok(),
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.n {
+            N::PosInt(n) => Some(n),
+            N::NegInt(_) | N::Float(..) => None,
+            N::Decimal(_) => self.to_u64().ok(),
+        }
+    }
+
+    /// Returns the number as an `f64`. This may lose precision, use [`Number::to_f64`]
+    /// to find out when it does.
+    pub fn as_f64(&self) -> Option<f64> {
+        match &self.n {
+            N::PosInt(n) => Some(*n as f64),
+            N::NegInt(n) => Some(*n as f64),
+            N::Float(f, _) => Some(*f),
+            N::Decimal(lexeme) => lexeme.parse().ok(),
+        }
+    }
+
+    /// Returns the text of the number when it is known.
+    ///
+    /// This is the source lexeme for decimals and parsed floats, integers return `None`
+    /// as they are always written the same way.
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.n {
+            N::Float(_, lexeme) => lexeme.as_deref(),
+            N::Decimal(lexeme) => Some(lexeme),
+            N::PosInt(_) | N::NegInt(_) => None,
+        }
+    }
+
+    /// Converts the number into an `f64` failing if the value can't be represented exactly.
+    pub fn to_f64(&self) -> Result<f64, NumberError> {
+        let (f, lexeme) = match &self.n {
+            N::Float(f, None) => return Ok(*f),
+            N::Float(f, Some(lexeme)) => (*f, lexeme.to_string()),
+            N::Decimal(lexeme) => (
+                lexeme.parse::<f64>().unwrap_or(f64::NAN),
+                lexeme.to_string(),
+            ),
+            N::PosInt(n) => (*n as f64, n.to_string()),
+            N::NegInt(n) => (*n as f64, n.to_string()),
+        };
+
+        if !f.is_finite() {
+            return Err(NumberError::OutOfRange(lexeme, "f64"));
+        }
+
+        // every f64 has a finite decimal expansion of at most 767 significant digits
+        if Digits::new(&lexeme) != Digits::new(&format!("{:.767e}", f)) {
+            return Err(NumberError::PrecisionLoss(lexeme, "f64"));
+        }
+
+        Ok(f)
+    }
+
+    /// Converts the number into an `i64` failing if it isn't an integer or is out of range.
+    pub fn to_i64(&self) -> Result<i64, NumberError> {
+        self.to_integer("i64")
+    }
+
+    /// Converts the number into an `u64` failing if it isn't an integer or is out of range.
+    pub fn to_u64(&self) -> Result<u64, NumberError> {
+        self.to_integer("u64")
+    }
+
+    /// Converts the number into an `i128` failing if it isn't an integer or is out of range.
+    pub fn to_i128(&self) -> Result<i128, NumberError> {
+        self.to_integer("i128")
+    }
+
+    /// Converts the number into an `u128` failing if it isn't an integer or is out of range.
+    pub fn to_u128(&self) -> Result<u128, NumberError> {
+        self.to_integer("u128")
+    }
+
+    /// Compares numbers by their value so `1` and `1.0` are equal. Numbers too large for
+    /// 64 bit integers are compared as `f64`.
+    pub(crate) fn compare(&self, other: &Number) -> Option<Ordering> {
+        if let (Some(a), Some(b)) = (self.as_i64(), other.as_i64()) {
+            return Some(a.cmp(&b));
+        }
+
+        if let (Some(a), Some(b)) = (self.as_u64(), other.as_u64()) {
+            return Some(a.cmp(&b));
+        }
+
+        self.as_f64()?.partial_cmp(&other.as_f64()?)
+    }
+
+    /// Whether the number is kept as an integer, or would be without `arbitrary_precision`.
+    fn is_integer(&self) -> bool {
+        match &self.n {
+            N::PosInt(_) | N::NegInt(_) => true,
+            N::Float(..) => false,
+            N::Decimal(lexeme) => {
+                !lexeme.contains(['.', 'e', 'E'])
+                    && (self.as_i64().is_some() || self.as_u64().is_some())
+            }
+        }
+    }
+
+    fn to_integer<T: FromStr>(&self, typ: &'static str) -> Result<T, NumberError> {
+        let digits = match self.n {
+            N::PosInt(n) => n.to_string(),
+            N::NegInt(n) => n.to_string(),
+            _ => self.integer_digits(typ)?,
+        };
+
+        digits
+            .parse()
+            .map_err(|_| NumberError::OutOfRange(self.to_string(), typ))
+    }
+
+    /// Expands the lexeme into plain integer digits ready to be parsed into `typ`.
+    fn integer_digits(&self, typ: &'static str) -> Result<String, NumberError> {
+        let lexeme = self.to_string();
+        let digits = Digits::new(&lexeme);
+
+        if digits.is_zero() {
+            return Ok("0".to_string());
+        }
+
+        if digits.point < digits.digits.len() as i64 {
+            return Err(NumberError::NotAnInteger(lexeme));
+        }
+
+        // anything this long can't fit in 128 bits anyway
+        if digits.point > 40 {
+            return Err(NumberError::OutOfRange(lexeme, typ));
+        }
+
+        let mut result = String::new();
+
+        if digits.negative {
+            result.push('-');
+        }
+
+        result.push_str(&digits.digits);
+
+        for _ in digits.digits.len() as i64..digits.point {
+            result.push('0');
+        }
+
+        Ok(result)
+    }
+}
+
+/// A normalized view of a number lexeme so that numbers can be compared without arithmetic.
+///
+/// The value is `0.{digits} * 10^point` where `digits` has no leading or trailing zeros.
+#[derive(Debug, PartialEq)]
+struct Digits {
+    negative: bool,
+    digits: String,
+    point: i64,
+}
+
+impl Digits {
+    fn new(lexeme: &str) -> Self {
+        let negative = lexeme.starts_with('-');
+        let lexeme = lexeme.trim_start_matches('-');
+
+        let (mantissa, exponent) = match lexeme.find(['e', 'E']) {
+            Some(index) => (&lexeme[..index], &lexeme[index + 1..]),
+            None => (lexeme, "0"),
+        };
+
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+        // exponents too large for an i64 are clamped since they are out of range either way
+        let exponent: i64 = exponent.parse().unwrap_or(if exponent.starts_with('-') {
+            i64::MIN / 2
+        } else {
+            i64::MAX / 2
+        });
+
+        let mut digits = format!("{}{}", integer, fraction);
+        let mut point = integer.len() as i64 + exponent;
+
+        let leading_zeros = digits.len() - digits.trim_start_matches('0').len();
+        digits.drain(..leading_zeros);
+        point -= leading_zeros as i64;
+
+        digits.truncate(digits.trim_end_matches('0').len());
+
+        if digits.is_empty() {
+            point = 0;
+        }
+
+        Self {
+            negative,
+            digits,
+            point,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.n, &other.n) {
+            (N::PosInt(a), N::PosInt(b)) => a == b,
+            (N::NegInt(a), N::NegInt(b)) => a == b,
+            (N::Float(a, _), N::Float(b, _)) => a == b,
+            (N::Decimal(a), N::Decimal(b)) => Digits::new(a) == Digits::new(b),
+            // equal when parsing without `arbitrary_precision` would make them equal, so
+            // integers are never equal to floats
+            (N::Decimal(_), _) | (_, N::Decimal(_)) => {
+                self.is_integer() == other.is_integer()
+                    && self.compare(other) == Some(Ordering::Equal)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Writes the number exactly as it appeared in the source when it was parsed.
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.n {
+            N::PosInt(n) => write!(f, "{}", n),
+            N::NegInt(n) => write!(f, "{}", n),
+            N::Float(_, Some(lexeme)) | N::Decimal(lexeme) => f.write_str(lexeme),
+            // debug formatting keeps the `.0` and switches to exponents for large values
+            N::Float(n, None) => write!(f, "{:?}", n),
+        }
+    }
+}
+
+macro_rules! from_unsigned {
+    ($($typ:ty),*) => {
+        $(
+            impl From<$typ> for Number {
+                fn from(n: $typ) -> Self {
+                    Self { n: N::PosInt(n as u64) }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! from_signed {
+    ($($typ:ty),*) => {
+        $(
+            impl From<$typ> for Number {
+                fn from(n: $typ) -> Self {
+                    if n < 0 {
+                        Self { n: N::NegInt(n as i64) }
+                    } else {
+                        Self { n: N::PosInt(n as u64) }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_unsigned!(u8, u16, u32, u64, usize);
+from_signed!(i8, i16, i32, i64, isize);
+
+impl From<u128> for Number {
+    fn from(n: u128) -> Self {
+        match u64::try_from(n) {
+            Ok(n) => Self::from(n),
+            Err(_) => Self::from_decimal_lexeme(&n.to_string()),
+        }
+    }
+}
+
+impl From<i128> for Number {
+    fn from(n: i128) -> Self {
+        match i64::try_from(n) {
+            Ok(n) => Self::from(n),
+            Err(_) => Self::from_decimal_lexeme(&n.to_string()),
+        }
+    }
+}
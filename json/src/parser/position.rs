@@ -0,0 +1,75 @@
+use std::fmt;
+
+/// A location in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    /// Byte offset from the start of the source
+    pub offset: usize,
+    /// 1 based line number
+    pub line: usize,
+    /// 1 based column counted in the configured [`ColumnUnit`]
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize, column: usize) -> Self {
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+
+    /// Moves a position within a piece of a larger source starting at `start`
+    /// to the same place in the larger source.
+    pub(crate) fn relocate(mut self, start: Position) -> Self {
+        if self.line == 1 {
+            self.column += start.column - 1;
+        }
+
+        self.line += start.line - 1;
+        self.offset += start.offset;
+
+        self
+    }
+}
+
+/// Writes the position as `line:column`
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The unit columns are counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnUnit {
+    /// UTF-8 bytes
+    Byte,
+    /// Unicode scalar values
+    Char,
+    /// UTF-16 code units, this is what the Language Server Protocol uses by default
+    Utf16,
+    /// Unicode scalar values where tabs move to the next tab stop set by `tab_width`
+    #[default]
+    Display,
+}
+
+impl ColumnUnit {
+    /// Returns the column after `c` when `c` starts at `column`.
+    pub fn advance(&self, c: char, column: usize, tab_width: usize) -> usize {
+        match self {
+            ColumnUnit::Byte => column + c.len_utf8(),
+            ColumnUnit::Char => column + 1,
+            ColumnUnit::Utf16 => column + c.len_utf16(),
+            ColumnUnit::Display => {
+                if c == '\t' && tab_width > 0 {
+                    // columns are 1 based so tab stops are at 1, 1 + tab_width, ...
+                    column + tab_width - (column - 1) % tab_width
+                } else {
+                    column + 1
+                }
+            }
+        }
+    }
+}
@@ -1,333 +1,838 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{self, Empty, Read},
+};
+
 use super::{
     error::Error,
+    number::Number,
+    position::Position,
     tokens::{keywords, Literal, Token, TokenType},
-    utils::{is_alpha, is_alphanumeric, is_digit},
+    utils::{is_digit, is_identifier_part, is_identifier_start},
     Options,
 };
 
-pub struct Scanner<'a> {
-    source: String,
-    tokens: Vec<Token>,
+/// Size of the chunks read from a reader by [`Scanner::from_reader`]
+pub const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Turns source text into [`Token`]s.
+///
+/// The scanner either works on a borrowed `&str` with [`Scanner::scan`] or pulls
+/// chunks from a [`Read`] with [`Scanner::from_reader`]. Tokens scanned from a
+/// `&str` borrow their lexemes and strings from it. When reading, input that has
+/// already been scanned is dropped so only the token currently being scanned and
+/// the chunk it came from are kept in memory.
+pub struct Scanner<'a, R = Empty> {
+    /// Borrowed unless the input is read in chunks
+    source: Cow<'a, str>,
     current: usize,
     line: usize,
     column: usize,
     start: usize,
-    options: &'a Options,
+    /// Offset of `source` in the whole input since scanned input is dropped
+    base: usize,
+    options: Options,
+    keywords: HashMap<&'static str, TokenType>,
+    reader: Option<R>,
+    chunk: Vec<u8>,
+    /// Bytes of a UTF-8 sequence that was split between chunks
+    pending: Vec<u8>,
+    /// An error reading from `reader`, surfaced by the next call to `next_token`
+    error: Option<Error>,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(options: &'a Options) -> Self {
+    pub fn new(options: &Options) -> Self {
         Self {
-            source: String::new(),
+            source: Cow::Borrowed(""),
             current: 0,
             line: 1,
             column: 1,
             start: 0,
-            tokens: vec![],
-            options,
+            base: 0,
+            options: options.clone(),
+            keywords: keywords(),
+            reader: None,
+            chunk: vec![],
+            pending: vec![],
+            error: None,
+        }
+    }
+
+    /// Creates a scanner over `source` to pull tokens from with [`Scanner::next_token`].
+    pub fn from_source(source: &'a str, options: &Options) -> Self {
+        let mut scanner = Self::new(options);
+
+        scanner.source = Cow::Borrowed(source);
+
+        scanner
+    }
+
+    /// Scans the whole source into a list of tokens ending with [`TokenType::Eof`].
+    pub fn scan(&mut self, source: &'a str) -> Result<Vec<Token<'a>>, Error> {
+        self.source = Cow::Borrowed(source);
+        self.current = 0;
+        self.base = 0;
+        self.line = 1;
+        self.column = 1;
+
+        let mut tokens = vec![];
+
+        loop {
+            let token = self.next_token()?;
+
+            let is_eof = token.typ == TokenType::Eof;
+
+            tokens.push(token);
+
+            if is_eof {
+                break;
+            }
         }
+
+        Ok(tokens)
     }
+}
 
-    pub fn scan(&mut self, source: String) -> Result<Vec<Token>, Error> {
-        self.source = source;
+impl<R: Read> Scanner<'static, R> {
+    /// Creates a scanner that reads the source from `reader` in chunks of [`CHUNK_SIZE`] bytes.
+    ///
+    /// Since the input is dropped as it is scanned the tokens own their contents.
+    pub fn from_reader(reader: R, options: &Options) -> Self {
+        Self::with_chunk_size(reader, options, CHUNK_SIZE)
+    }
 
-        let keywords = keywords();
+    /// Creates a scanner that reads the source from `reader` in chunks of `chunk_size` bytes.
+    pub fn with_chunk_size(reader: R, options: &Options, chunk_size: usize) -> Self {
+        Self {
+            source: Cow::Owned(String::new()),
+            current: 0,
+            line: 1,
+            column: 1,
+            start: 0,
+            base: 0,
+            options: options.clone(),
+            keywords: keywords(),
+            reader: Some(reader),
+            chunk: vec![0; chunk_size.max(1)],
+            pending: vec![],
+            error: None,
+        }
+    }
+}
+
+impl<'a, R: Read> Scanner<'a, R> {
+    /// Returns an iterator over the remaining tokens leaving out the final [`TokenType::Eof`].
+    pub fn tokens(&mut self) -> Tokens<'_, 'a, R> {
+        Tokens {
+            scanner: self,
+            done: false,
+        }
+    }
+
+    /// Scans the next token. Once the source is exhausted this keeps returning [`TokenType::Eof`].
+    pub fn next_token(&mut self) -> Result<Token<'a>, Error> {
+        let token = self.scan_token();
+
+        // a failed read cuts the source short which would otherwise look like a syntax error
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        token
+    }
+
+    /// Skips everything up to the next record separator so scanning can continue with
+    /// the next record of an RFC 7464 sequence after an error.
+    pub(crate) fn skip_record(&mut self) {
+        while !self.is_at_end() && self.peek() != '\u{1e}' {
+            if self.peek() == '\n' || self.peek() == '\r' {
+                self.new_line();
+            } else {
+                self.advance();
+            }
+        }
+    }
+
+    fn scan_token(&mut self) -> Result<Token<'a>, Error> {
+        loop {
+            self.discard();
+
+            if self.is_at_end() {
+                return Ok(Token {
+                    typ: TokenType::Eof,
+                    position: self.position(),
+                    lexeme: Cow::Borrowed("\0"),
+                    literal: None,
+                });
+            }
 
-        while !self.is_at_end() {
             self.start = self.current;
 
-            let c = self.source.as_bytes()[self.current];
+            let c = self.peek();
 
             match c {
-                b'{' => self.add_token(TokenType::LeftBrace),
-                b'}' => self.add_token(TokenType::RightBrace),
-                b':' => self.add_token(TokenType::Colon),
-                b',' => self.add_token(TokenType::Comma),
-                b'[' => self.add_token(TokenType::LeftSquareBracket),
-                b']' => self.add_token(TokenType::RightSquareBracket),
-                b'/' => {
-                    if self.options.allow_comments {
-                        if self.peek_next() == b'/' {
-                            self.advance(1);
-
-                            if self.peek() == b'/' {
-                                self.advance(1);
-                            } else {
-                                continue;
-                            }
-
-                            while self.peek() != b'\n' && !self.is_at_end() {
-                                self.advance(1);
-                            }
-                        } else if self.peek_next() == b'*' {
-                            let start_line = self.line;
-                            let start_column = self.column;
-
-                            // stack allows for possibly nested comments
-                            let mut stack = 1;
-
-                            self.advance(1);
-                            self.advance(1);
-
-                            while !self.is_at_end() {
-                                // check for terminating comment
-                                if self.peek() == b'*' && self.peek_next() == b'/' {
-                                    stack = stack - 1;
-
-                                    self.advance(1);
-                                    self.advance(1);
-
-                                    if stack > 0 {
-                                        continue;
-                                    } else {
-                                        break;
-                                    }
-                                }
-
-                                if self.peek() == b'\n' {
-                                    // handle new line
-                                    self.new_line();
-                                }
-
-                                // adds a new nested comment to the stack
-                                if self.peek() == b'/' && self.peek_next() == b'*' {
-                                    stack = stack + 1;
-                                }
-
-                                self.advance(1);
-                            }
-
-                            if self.is_at_end() && stack > 0 {
-                                return Err(Error::SyntaxError(format!(
-                                    "{}:{}: Unterminated comment.",
-                                    start_line, start_column
-                                )));
-                            }
-                        }
+                '{' => return Ok(self.token(TokenType::LeftBrace)),
+                '}' => return Ok(self.token(TokenType::RightBrace)),
+                ':' => return Ok(self.token(TokenType::Colon)),
+                ',' => return Ok(self.token(TokenType::Comma)),
+                '[' => return Ok(self.token(TokenType::LeftSquareBracket)),
+                ']' => return Ok(self.token(TokenType::RightSquareBracket)),
+                '\u{1e}' => return Ok(self.token(TokenType::RecordSeparator)),
+                '/' if self.options.allow_comments => self.comment()?,
+                '"' => return self.string('"'),
+                '\'' if self.options.allow_single_quotes => return self.string('\''),
+                '\n' | '\r' => self.new_line(),
+                // skip whitespace
+                ' ' | '\t' => {
+                    self.advance();
+                }
+                _ => {
+                    if is_digit(&c) || c == '-' {
+                        return self.number();
+                    } else if is_identifier_start(&c) || c == '\\' {
+                        return self.identifier();
                     } else {
-                        return Err(Error::SyntaxError(format!(
-                            "Unexpected token '{}' at {}:{}.",
-                            *&c as char, self.line, self.column
-                        )));
+                        return Err(Error::syntax(
+                            format!("Unexpected token '{}'.", c),
+                            self.position(),
+                        ));
                     }
                 }
-                b'"' | b'\'' => {
-                    if c == b'"' {
-                        let start_line = self.line;
-                        let start_column = self.column;
-                        self.advance(1);
-
-                        // handles escaped '"'
-                        while !(self.peek() == b'"' && self.peek_last() != b'\\')
-                            && !self.is_at_end()
-                        {
-                            self.advance(1);
-                        }
+            }
+        }
+    }
 
-                        if self.is_at_end() {
-                            return Err(Error::SyntaxError(format!(
-                                "{}:{} Unclosed '\"'",
-                                start_line, start_column
-                            )));
-                        }
+    /// Skips over a `//` or `/* */` comment. Block comments may be nested.
+    fn comment(&mut self) -> Result<(), Error> {
+        let start = self.position();
 
-                        let result = self.source[self.start + 1..self.current].to_string();
-
-                        // go past char
-                        self.advance(1);
-
-                        self.tokens.push(Token {
-                            typ: TokenType::String,
-                            column: start_column,
-                            line: start_line,
-                            lexeme: self.source[self.start..self.current].to_string(),
-                            literal: Some(Literal::String(result)),
-                        });
-                    } else if c == b'\'' && self.options.allow_single_quotes {
-                        let start_line = self.line;
-                        let start_column = self.column;
-                        self.advance(1);
-
-                        // handles escaped "'"
-                        while !(self.peek() == b'\'' && self.peek_last() != b'\\')
-                            && !self.is_at_end()
-                        {
-                            self.advance(1);
-                        }
+        if self.peek_next() == '/' {
+            while self.peek() != '\n' && self.peek() != '\r' && !self.is_at_end() {
+                self.advance();
+            }
+        } else if self.peek_next() == '*' {
+            // stack allows for possibly nested comments
+            let mut stack = 1;
 
-                        if self.is_at_end() {
-                            return Err(Error::SyntaxError(format!(
-                                "{}:{} Unclosed \"'\"",
-                                start_line, start_column
-                            )));
-                        }
+            self.advance();
+            self.advance();
 
-                        let result = self.source[self.start + 1..self.current].to_string();
+            while !self.is_at_end() {
+                // check for terminating comment
+                if self.peek() == '*' && self.peek_next() == '/' {
+                    stack -= 1;
 
-                        // go past char
-                        self.advance(1);
+                    self.advance();
+                    self.advance();
 
-                        self.tokens.push(Token {
-                            typ: TokenType::String,
-                            column: start_column,
-                            line: start_line,
-                            lexeme: self.source[self.start..self.current].to_string(),
-                            literal: Some(Literal::String(result)),
-                        });
+                    if stack > 0 {
+                        continue;
                     } else {
-                        return Err(Error::SyntaxError(format!(
-                            "Unexpected token '{}' at {}:{}.",
-                            *&c as char, self.line, self.column
-                        )));
+                        break;
                     }
                 }
-                b'\n' => self.new_line(),
-                // skip whitespace
-                b' ' | b'\r' | b'\t' => {
-                    if c == b'\t' {
-                        self.advance(self.options.tab_width);
-                    } else {
-                        self.advance(1);
-                    }
+
+                // adds a new nested comment to the stack
+                if self.peek() == '/' && self.peek_next() == '*' {
+                    stack += 1;
+
+                    self.advance();
+                    self.advance();
+
+                    continue;
                 }
-                _ => {
-                    let start_line = self.line;
-                    let start_column = self.column;
-                    if is_digit(&c) || c == b'-' {
-                        while is_digit(&self.peek()) || self.peek() == b'-' {
-                            self.advance(1);
-                        }
 
-                        if self.peek() == b'.' && is_digit(&self.peek_next()) {
-                            self.advance(1);
+                if self.peek() == '\n' || self.peek() == '\r' {
+                    self.new_line();
+                } else {
+                    self.advance();
+                }
+            }
 
-                            while is_digit(&self.peek()) {
-                                self.advance(1);
-                            }
-                        }
+            if stack > 0 {
+                return Err(Error::syntax("Unterminated comment.", start));
+            }
+        } else {
+            return Err(Error::syntax("Unexpected token '/'.", start));
+        }
 
-                        if self.peek() == b'e' || self.peek() == b'E' {
-                            self.advance(1);
-                            if self.peek() == b'+' || self.peek() == b'-' {
-                                self.advance(1);
-                            }
+        Ok(())
+    }
 
-                            while is_digit(&self.peek()) {
-                                self.advance(1);
-                            }
-                        }
+    /// Scans a keyword or an unquoted key following the ECMAScript IdentifierName grammar
+    ///
+    /// ```text
+    /// identifier -> start part*
+    /// start      -> ID_Start | "$" | "_" | escape
+    /// part       -> ID_Continue | "$" | ZWNJ | ZWJ | escape
+    /// escape     -> "\u" hex hex hex hex | "\u{" hex+ "}"
+    /// ```
+    fn identifier(&mut self) -> Result<Token<'a>, Error> {
+        let start = self.position();
+
+        // only allocated once an escape has to be decoded
+        let mut name: Option<String> = None;
+
+        loop {
+            let c = self.peek();
+
+            let is_first = self.current == self.start;
+
+            let (c, escaped) = if c == '\\' {
+                name.get_or_insert_with(|| self.source[self.start..self.current].to_string());
+
+                (self.identifier_escape()?, true)
+            } else {
+                (c, false)
+            };
+
+            let valid = if is_first {
+                is_identifier_start(&c)
+            } else {
+                is_identifier_part(&c)
+            };
+
+            if !valid {
+                if escaped || is_first {
+                    return Err(Error::syntax(
+                        format!("Invalid character '{}' in identifier.", c.escape_debug()),
+                        start,
+                    ));
+                }
+
+                break;
+            }
+
+            if !escaped {
+                self.advance();
+            }
+
+            if let Some(name) = name.as_mut() {
+                name.push(c);
+            }
+        }
+
+        let lexeme = self.slice(self.start, self.current);
+
+        // escaped keywords are never treated as keywords like in JavaScript
+        if let Some(typ) = self
+            .keywords
+            .get(lexeme.as_ref())
+            .filter(|_| name.is_none())
+        {
+            Ok(Token {
+                typ: *typ,
+                position: start,
+                literal: None,
+                lexeme,
+            })
+        } else if self.options.allow_unquoted_identifier {
+            let name = match name {
+                Some(name) => Cow::Owned(name),
+                None => lexeme.clone(),
+            };
+
+            Ok(Token {
+                typ: TokenType::Identifier,
+                position: start,
+                literal: Some(Literal::String(name)),
+                lexeme,
+            })
+        } else {
+            Err(Error::syntax(
+                format!("Unquoted identifiers are not allowed '{}'.", lexeme),
+                start,
+            ))
+        }
+    }
+
+    /// Decodes a `\uXXXX` or `\u{X}` escape inside of an identifier.
+    fn identifier_escape(&mut self) -> Result<char, Error> {
+        let start = self.position();
+
+        // go past '\'
+        self.advance();
+
+        if self.peek() != 'u' {
+            return Err(Error::syntax(
+                "Expected unicode escape sequence in identifier.",
+                start,
+            ));
+        }
+
+        self.advance();
+
+        let code_point = if self.peek() == '{' {
+            self.advance();
+
+            let mut code_point: u32 = 0;
+            let mut digits = 0;
+
+            while let Some(digit) = self.peek().to_digit(16) {
+                code_point = code_point.saturating_mul(16).saturating_add(digit);
+                digits += 1;
+                self.advance();
+            }
+
+            if digits == 0 || self.peek() != '}' {
+                return Err(Error::syntax(
+                    "Invalid unicode escape sequence, expected '\\u{' hex digits '}'.",
+                    start,
+                ));
+            }
+
+            self.advance();
+
+            code_point
+        } else {
+            self.hex_escape(start)?
+        };
+
+        // surrogates and anything past U+10FFFF aren't valid on their own
+        char::from_u32(code_point).ok_or_else(|| {
+            Error::syntax(
+                format!("Invalid code point U+{:04X} in identifier.", code_point),
+                start,
+            )
+        })
+    }
+
+    /// Scans a number following the ECMA-404 grammar
+    ///
+    /// ```text
+    /// number -> "-"? int frac? exp?
+    /// int    -> "0" | [1-9] [0-9]*
+    /// frac   -> "." [0-9]+
+    /// exp    -> ("e" | "E") ("+" | "-")? [0-9]+
+    /// ```
+    fn number(&mut self) -> Result<Token<'a>, Error> {
+        let start = self.position();
+
+        if self.peek() == '-' {
+            self.advance();
+        }
+
+        if self.peek() == '0' {
+            self.advance();
+
+            if is_digit(&self.peek()) {
+                return Err(Error::syntax(
+                    "Leading zeros are not allowed in numbers.",
+                    start,
+                ));
+            }
+        } else if is_digit(&self.peek()) {
+            while is_digit(&self.peek()) {
+                self.advance();
+            }
+        } else {
+            return Err(Error::syntax("Expected digit after '-'.", self.position()));
+        }
+
+        let mut is_integer = true;
 
-                        let literal = &self.source[self.start..self.current];
+        if self.peek() == '.' {
+            is_integer = false;
+            self.advance();
 
-                        let number: f64 = literal.parse().unwrap();
+            if !is_digit(&self.peek()) {
+                return Err(Error::syntax("Expected digit after '.'.", self.position()));
+            }
+
+            while is_digit(&self.peek()) {
+                self.advance();
+            }
+        }
+
+        if self.peek() == 'e' || self.peek() == 'E' {
+            is_integer = false;
+            self.advance();
+
+            if self.peek() == '+' || self.peek() == '-' {
+                self.advance();
+            }
+
+            if !is_digit(&self.peek()) {
+                return Err(Error::syntax(
+                    "Expected digit in exponent.",
+                    self.position(),
+                ));
+            }
+
+            while is_digit(&self.peek()) {
+                self.advance();
+            }
+        }
+
+        let lexeme = self.slice(self.start, self.current);
+
+        let number = if self.options.arbitrary_precision {
+            Some(Number::from_decimal_lexeme(&lexeme))
+        } else if is_integer {
+            Number::from_integer_lexeme(&lexeme)
+        } else {
+            Number::from_float_lexeme(&lexeme)
+        };
+
+        let number = match number {
+            Some(number) => number,
+            None => {
+                return Err(Error::syntax(
+                    format!("Number '{}' is out of range.", lexeme),
+                    start,
+                ));
+            }
+        };
+
+        Ok(Token {
+            typ: TokenType::Number,
+            position: start,
+            literal: Some(Literal::Number(number)),
+            lexeme,
+        })
+    }
+
+    /// Scans a string delimited by `quote` decoding escape sequences as it goes.
+    ///
+    /// The token's `lexeme` keeps the original source text (quotes included) while
+    /// the literal holds the decoded string. The literal only needs its own allocation
+    /// when the string contains escape sequences.
+    fn string(&mut self, quote: char) -> Result<Token<'a>, Error> {
+        let start = self.position();
+
+        // go past the opening quote
+        self.advance();
+
+        let content_start = self.current;
+
+        // only allocated once an escape has to be decoded
+        let mut result: Option<String> = None;
+
+        loop {
+            if self.is_at_end() {
+                return Err(Error::syntax(format!("Unclosed '{}'.", quote), start));
+            }
+
+            let c = self.peek();
+
+            if c == quote {
+                break;
+            }
+
+            if c == '\\' {
+                let result = result
+                    .get_or_insert_with(|| self.source[content_start..self.current].to_string());
+
+                self.escape(result)?;
+            } else if c < '\u{20}' {
+                return Err(Error::syntax(
+                    format!("Unescaped control character U+{:04X} in string.", c as u32),
+                    self.position(),
+                ));
+            } else {
+                // copy everything up to the next quote, escape or control character at once
+                let run_start = self.current;
+
+                while !self.is_at_end()
+                    && self.peek() != quote
+                    && self.peek() != '\\'
+                    && self.peek() >= '\u{20}'
+                {
+                    self.advance();
+                }
 
-                        self.tokens.push(Token {
-                            typ: TokenType::Number,
-                            column: start_column,
-                            line: start_line,
-                            literal: Some(Literal::Number(number)),
-                            lexeme: literal.to_string(),
-                        });
-                    } else if is_alpha(&c) {
-                        while is_alphanumeric(&self.peek()) {
-                            self.advance(1);
+                if let Some(result) = result.as_mut() {
+                    result.push_str(&self.source[run_start..self.current]);
+                }
+            }
+        }
+
+        let literal = match result {
+            Some(result) => Cow::Owned(result),
+            None => self.slice(content_start, self.current),
+        };
+
+        // go past the closing quote
+        self.advance();
+
+        Ok(Token {
+            typ: TokenType::String,
+            position: start,
+            lexeme: self.slice(self.start, self.current),
+            literal: Some(Literal::String(literal)),
+        })
+    }
+
+    /// Decodes the escape sequence starting at the current `\` onto `result`.
+    fn escape(&mut self, result: &mut String) -> Result<(), Error> {
+        let start = self.position();
+
+        // go past '\'
+        self.advance();
+
+        if self.is_at_end() {
+            return Err(Error::syntax("Unterminated escape sequence.", start));
+        }
+
+        match self.peek() {
+            '"' => result.push('"'),
+            '\\' => result.push('\\'),
+            '/' => result.push('/'),
+            'b' => result.push('\u{8}'),
+            'f' => result.push('\u{c}'),
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            // only valid when strings can be single quoted
+            '\'' if self.options.allow_single_quotes => result.push('\''),
+            'u' => {
+                self.advance();
+
+                let unit = self.hex_escape(start)?;
+
+                let code_point = match unit {
+                    0xD800..=0xDBFF => {
+                        // a high surrogate has to be followed by an escaped low surrogate
+                        let low_start = self.position();
+
+                        if self.peek() != '\\' || self.peek_next() != 'u' {
+                            return Err(Error::syntax(
+                                format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                                start,
+                            ));
                         }
 
-                        let identifier = &self.source[self.start..self.current];
-
-                        if let Some(typ) = keywords.get(identifier) {
-                            self.tokens.push(Token {
-                                typ: typ.clone(),
-                                column: start_column,
-                                line: start_line,
-                                literal: None,
-                                lexeme: identifier.to_string(),
-                            });
-                        } else {
-                            if self.options.allow_unquoted_identifier {
-                                // allow option
-                                self.tokens.push(Token {
-                                    typ: TokenType::Identifier,
-                                    column: start_column,
-                                    line: start_line,
-                                    literal: None,
-                                    lexeme: identifier.to_string(),
-                                });
-                            } else {
-                                return Err(Error::SyntaxError(format!(
-                                    "Unquoted identifiers are not allowed '{}' at {}:{}.",
-                                    identifier, start_line, start_column
-                                )));
-                            }
+                        self.advance();
+                        self.advance();
+
+                        let low = self.hex_escape(low_start)?;
+
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(Error::syntax(
+                                format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                                start,
+                            ));
                         }
-                    } else {
-                        return Err(Error::SyntaxError(format!(
-                            "Unexpected token '{}' at {}:{}.",
-                            *&c as char, self.line, self.column
-                        )));
+
+                        0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
                     }
-                }
+                    0xDC00..=0xDFFF => {
+                        return Err(Error::syntax(
+                            format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                            start,
+                        ));
+                    }
+                    _ => unit,
+                };
+
+                // surrogates have been handled above so this is always a valid scalar value
+                result.push(char::from_u32(code_point).unwrap());
+
+                return Ok(());
+            }
+            _ => {
+                return Err(Error::syntax(
+                    format!("Invalid escape sequence '\\{}'.", self.peek()),
+                    start,
+                ));
             }
         }
 
-        self.tokens.push(Token {
-            typ: TokenType::Eof,
-            line: self.line,
-            column: self.column,
-            lexeme: "\0".to_string(),
-            literal: None,
-        });
+        self.advance();
 
-        Ok(self.tokens.clone())
+        Ok(())
     }
 
-    // fn number(&mut self) -> f64 {
+    /// Reads the 4 hex digits of a `\uXXXX` escape returning the UTF-16 code unit.
+    fn hex_escape(&mut self, position: Position) -> Result<u32, Error> {
+        let mut unit = 0;
 
-    // }
+        for _ in 0..4 {
+            match self.peek().to_digit(16) {
+                Some(digit) => {
+                    unit = unit * 16 + digit;
+                    self.advance();
+                }
+                None => {
+                    return Err(Error::syntax(
+                        "Invalid unicode escape sequence, expected 4 hex digits.",
+                        position,
+                    ));
+                }
+            }
+        }
+
+        Ok(unit)
+    }
 
-    fn add_token(&mut self, typ: TokenType) {
-        self.tokens.push(Token {
-            column: self.column,
-            line: self.line,
-            lexeme: self.source[self.start..self.current + 1].to_string(),
+    /// Creates a token from the single character at `current`.
+    fn token(&mut self, typ: TokenType) -> Token<'a> {
+        let position = self.position();
+
+        self.advance();
+
+        Token {
+            position,
+            lexeme: self.slice(self.start, self.current),
             literal: None,
             typ,
-        });
-
-        self.advance(1);
+        }
     }
 
-    fn advance(&mut self, columns: usize) -> u8 {
-        self.current = self.current + 1;
-        self.column = self.column + columns;
+    fn advance(&mut self) -> char {
+        let c = self.peek();
+
+        self.current += c.len_utf8();
+        self.column = self
+            .options
+            .column_unit
+            .advance(c, self.column, self.options.tab_width);
 
-        self.source.as_bytes()[self.current - 1]
+        c
     }
 
+    /// Consumes a `\n`, `\r\n` or lone `\r` line break.
     fn new_line(&mut self) {
-        self.line = self.line + 1;
-        self.column = 0;
+        if self.peek() == '\r' && self.peek_next() == '\n' {
+            self.current += 1;
+        }
 
-        self.advance(1);
+        self.current += 1;
+        self.line += 1;
+        self.column = 1;
     }
 
-    fn peek_last(&self) -> u8 {
-        self.source.as_bytes()[self.current - 1]
+    /// Returns the source between `start` and `end`, borrowed unless it is being read in chunks.
+    fn slice(&self, start: usize, end: usize) -> Cow<'a, str> {
+        match &self.source {
+            Cow::Borrowed(source) => Cow::Borrowed(&source[start..end]),
+            Cow::Owned(source) => Cow::Owned(source[start..end].to_string()),
+        }
     }
 
-    fn peek(&self) -> u8 {
-        self.source.as_bytes()[self.current]
+    fn position(&self) -> Position {
+        Position::new(self.base + self.current, self.line, self.column)
     }
 
-    fn peek_next(&self) -> u8 {
-        if self.is_at_end() {
-            return b'\0';
-        }
+    fn peek(&mut self) -> char {
+        self.fill(1);
 
-        self.source.as_bytes()[self.current + 1]
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
-    fn is_at_end(&self) -> bool {
+    fn peek_next(&mut self) -> char {
+        let len = self.peek().len_utf8();
+
+        self.fill(len + 1);
+
+        let mut chars = self.source[self.current..].chars();
+
+        chars.next();
+
+        chars.next().unwrap_or('\0')
+    }
+
+    fn is_at_end(&mut self) -> bool {
+        self.fill(1);
+
         self.current >= self.source.len()
     }
+
+    /// Reads chunks from the reader until at least `bytes` bytes after `current` are
+    /// buffered or the reader is exhausted.
+    fn fill(&mut self, bytes: usize) {
+        while self.source.len() < self.current + bytes {
+            let reader = match self.reader.as_mut() {
+                Some(reader) => reader,
+                None => return,
+            };
+
+            match reader.read(&mut self.chunk) {
+                Ok(0) => {
+                    self.reader = None;
+
+                    if !self.pending.is_empty() {
+                        self.error = Some(invalid_utf8());
+                    }
+                }
+                Ok(read) => {
+                    self.pending.extend_from_slice(&self.chunk[..read]);
+
+                    let valid = match std::str::from_utf8(&self.pending) {
+                        Ok(str) => str.len(),
+                        // the sequence may continue in the next chunk
+                        Err(err) if err.error_len().is_none() => err.valid_up_to(),
+                        Err(_) => {
+                            self.reader = None;
+                            self.error = Some(invalid_utf8());
+
+                            return;
+                        }
+                    };
+
+                    // this was just validated
+                    self.source
+                        .to_mut()
+                        .push_str(std::str::from_utf8(&self.pending[..valid]).unwrap());
+
+                    self.pending.drain(..valid);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.reader = None;
+                    self.error = Some(err.into());
+                }
+            }
+        }
+    }
+
+    /// Drops input that has already been scanned once enough of it has built up.
+    fn discard(&mut self) {
+        if self.reader.is_none() || self.current < self.chunk.len() {
+            return;
+        }
+
+        self.source.to_mut().drain(..self.current);
+        self.base += self.current;
+        self.current = 0;
+    }
+}
+
+fn invalid_utf8() -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    ))
+}
+
+/// Iterator over the tokens of a scanner created by [`Scanner::tokens`].
+pub struct Tokens<'s, 'a, R> {
+    scanner: &'s mut Scanner<'a, R>,
+    done: bool,
+}
+
+impl<'a, R: Read> Iterator for Tokens<'_, 'a, R> {
+    type Item = Result<Token<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.scanner.next_token() {
+            Ok(token) if token.typ == TokenType::Eof => {
+                self.done = true;
+
+                None
+            }
+            Ok(token) => Some(Ok(token)),
+            Err(err) => {
+                self.done = true;
+
+                Some(Err(err))
+            }
+        }
+    }
 }
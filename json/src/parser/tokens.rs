@@ -1,12 +1,16 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
+
+use super::{number::Number, position::Position};
 
 #[derive(Debug, Clone)]
-pub struct Token {
+pub struct Token<'a> {
     pub typ: TokenType,
-    pub lexeme: String,
-    pub line: usize,
-    pub column: usize,
-    pub literal: Option<Literal>,
+    /// The token exactly as it appears in the source.
+    /// For strings this is before any escape sequences are decoded.
+    pub lexeme: Cow<'a, str>,
+    /// Where the token starts in the source
+    pub position: Position,
+    pub literal: Option<Literal<'a>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -19,6 +23,8 @@ pub enum TokenType {
     LeftSquareBracket,
     RightSquareBracket,
     Comma,
+    /// U+001E used to separate RFC 7464 JSON text sequences
+    RecordSeparator,
     Eof,
 
     // Literals
@@ -32,14 +38,30 @@ pub enum TokenType {
 }
 
 #[derive(Debug, Clone)]
-pub enum Literal {
-    String(String),
-    Number(f64),
+pub enum Literal<'a> {
+    /// The decoded contents of a string or identifier, use [`Token::lexeme`] for the original text.
+    ///
+    /// This borrows from the source unless escape sequences had to be decoded.
+    String(Cow<'a, str>),
+    Number(Number),
     True,
     False,
     Null,
 }
 
+impl Literal<'_> {
+    /// Copies any borrowed string so the literal no longer depends on the source.
+    pub fn into_owned(self) -> Literal<'static> {
+        match self {
+            Literal::String(str) => Literal::String(Cow::Owned(str.into_owned())),
+            Literal::Number(num) => Literal::Number(num),
+            Literal::True => Literal::True,
+            Literal::False => Literal::False,
+            Literal::Null => Literal::Null,
+        }
+    }
+}
+
 pub fn keywords() -> HashMap<&'static str, TokenType> {
     let mut map: HashMap<&str, TokenType> = HashMap::new();
 
@@ -1,11 +1,17 @@
-pub fn is_digit(c: &u8) -> bool {
-    c >= &b'0' && c <= &b'9'
+pub fn is_digit(c: &char) -> bool {
+    c.is_ascii_digit()
 }
 
-pub fn is_alpha(c: &u8) -> bool {
-    (c >= &b'a' && c <= &b'z') || (c >= &b'A' && c <= &b'Z') || c == &b'_'
+/// Can `c` start an ECMAScript IdentifierName.
+///
+/// Unicode `ID_Start` is checked with its `XID_Start` closure which only differs for
+/// characters that change under NFKC normalization.
+pub fn is_identifier_start(c: &char) -> bool {
+    *c == '$' || *c == '_' || unicode_ident::is_xid_start(*c)
 }
 
-pub fn is_alphanumeric(c: &u8) -> bool {
-    is_alpha(c) || is_digit(c)
+/// Can `c` continue an ECMAScript IdentifierName.
+pub fn is_identifier_part(c: &char) -> bool {
+    // ZWNJ and ZWJ are allowed to continue identifiers
+    *c == '$' || *c == '\u{200C}' || *c == '\u{200D}' || unicode_ident::is_xid_continue(*c)
 }
@@ -0,0 +1,566 @@
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use super::{
+    map::Map,
+    number::Number,
+    utils::{is_identifier_part, is_identifier_start},
+};
+use crate::{
+    pointer::{JsonPointer, PointerError},
+    serializer,
+};
+
+/// A parsed JSON value.
+///
+/// Keys and strings borrow from the source they were parsed from unless they
+/// contained escape sequences, use [`Value::into_owned`] to detach it from the source.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Value<'a> {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Cow<'a, str>),
+    Array(Vec<Value<'a>>),
+    Object(Map<Cow<'a, str>, Value<'a>>),
+}
+
+/// Returned when indexing into something that isn't there.
+static NULL: Value<'static> = Value::Null;
+
+impl<'a> Value<'a> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Value::Bool(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Number(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, Value::String(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Value::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, Value::Object(_))
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(bool) => Some(*bool),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            Value::Number(num) => Some(num),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if it fits into an `i64`, see [`Number::as_i64`].
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    /// Returns the number if it fits into a `u64`, see [`Number::as_u64`].
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
+    /// Returns the number as an `f64` which may lose precision, see [`Number::as_f64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().and_then(Number::as_f64)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(str) => Some(str),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value<'a>>> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value<'a>>> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map<Cow<'a, str>, Value<'a>>> {
+        match self {
+            Value::Object(properties) => Some(properties),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<Cow<'a, str>, Value<'a>>> {
+        match self {
+            Value::Object(properties) => Some(properties),
+            _ => None,
+        }
+    }
+
+    /// Returns the property of an object by key or the element of an array by position.
+    ///
+    /// ```text
+    /// value.get("users").and_then(|users| users.get(0))
+    /// ```
+    pub fn get<I: ValueIndex>(&self, index: I) -> Option<&Value<'a>> {
+        index.index_into(self)
+    }
+
+    pub fn get_mut<I: ValueIndex>(&mut self, index: I) -> Option<&mut Value<'a>> {
+        index.index_into_mut(self)
+    }
+
+    /// Looks up a value by an RFC 6901 pointer like `/servers/0/host`, the empty pointer
+    /// is the value itself.
+    ///
+    /// ```text
+    /// let host = value.pointer("/servers/0/host")?.as_str();
+    ///
+    /// // Pointer Error: /servers/5: Index 5 is out of bounds for an array of length 2.
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Result<&Value<'a>, PointerError> {
+        JsonPointer::parse(pointer)?.get(self)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Result<&mut Value<'a>, PointerError> {
+        JsonPointer::parse(pointer)?.get_mut(self)
+    }
+
+    /// Adds `value` at `pointer` returning the value it replaced.
+    ///
+    /// Properties are set, elements are inserted before the position and `-` appends to
+    /// the array. Everything before the last segment has to exist already.
+    pub fn insert_at(
+        &mut self,
+        pointer: &str,
+        value: Value<'a>,
+    ) -> Result<Option<Value<'a>>, PointerError> {
+        JsonPointer::parse(pointer)?.insert(self, value)
+    }
+
+    /// Removes the value at `pointer`, the elements after it move down.
+    pub fn remove_at(&mut self, pointer: &str) -> Result<Value<'a>, PointerError> {
+        JsonPointer::parse(pointer)?.remove(self)
+    }
+
+    /// The name of the kind of value used in error messages.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+
+    /// Compares values the way JSON does: numbers are equal by their value so `1` and
+    /// `1.0` are the same, and objects are equal regardless of the order of their keys.
+    pub(crate) fn json_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(left), Value::Bool(right)) => left == right,
+            (Value::Number(left), Value::Number(right)) => {
+                left.compare(right) == Some(Ordering::Equal)
+            }
+            (Value::String(left), Value::String(right)) => left == right,
+            (Value::Array(left), Value::Array(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|(left, right)| left.json_eq(right))
+            }
+            (Value::Object(left), Value::Object(right)) => {
+                left.len() == right.len()
+                    && left.iter().all(|(key, left)| {
+                        right
+                            .get(key.as_ref())
+                            .is_some_and(|right| left.json_eq(right))
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Takes the value out leaving [`Value::Null`] in its place.
+    pub fn take(&mut self) -> Value<'a> {
+        std::mem::take(self)
+    }
+
+    /// Copies every borrowed key and string so the value can outlive its source.
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Value::Null => Value::Null,
+            Value::Bool(bool) => Value::Bool(bool),
+            Value::Number(num) => Value::Number(num),
+            Value::String(str) => Value::String(Cow::Owned(str.into_owned())),
+            Value::Array(values) => {
+                Value::Array(values.into_iter().map(Value::into_owned).collect())
+            }
+            Value::Object(properties) => {
+                let mut owned = Map::with_order(properties.order());
+
+                // appending keeps keys that are there more than once
+                for (key, value) in properties {
+                    owned.append(Cow::Owned(key.into_owned()), value.into_owned());
+                }
+
+                Value::Object(owned)
+            }
+        }
+    }
+}
+
+/// Writes the value as compact JSON, or indented by two spaces with `{:#}`.
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = if f.alternate() {
+            serializer::to_string_pretty(self)
+        } else {
+            serializer::to_string(self)
+        };
+
+        f.write_str(&json)
+    }
+}
+
+/// Something that can look up a child of a [`Value`], either a key of an object
+/// or a position in an array.
+pub trait ValueIndex {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>>;
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>>;
+
+    /// Used by [`IndexMut`], creating the child when it is missing where that makes sense.
+    fn index_or_insert<'v, 'a>(&self, value: &'v mut Value<'a>) -> &'v mut Value<'a>;
+}
+
+impl ValueIndex for usize {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        value.as_array().and_then(|values| values.get(*self))
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        value
+            .as_array_mut()
+            .and_then(|values| values.get_mut(*self))
+    }
+
+    /// Panics if `value` isn't an array or the position is out of bounds.
+    fn index_or_insert<'v, 'a>(&self, value: &'v mut Value<'a>) -> &'v mut Value<'a> {
+        match value {
+            Value::Array(values) => {
+                let len = values.len();
+
+                values.get_mut(*self).unwrap_or_else(|| {
+                    panic!("index {} out of bounds for array of length {}", self, len)
+                })
+            }
+            _ => panic!("cannot index into a non array value with {}", self),
+        }
+    }
+}
+
+impl ValueIndex for str {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        value
+            .as_object()
+            .and_then(|properties| properties.get(self))
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        value
+            .as_object_mut()
+            .and_then(|properties| properties.get_mut(self))
+    }
+
+    /// Inserts `null` for a missing key and turns `null` into an empty object first.
+    /// Panics for anything else that isn't an object.
+    fn index_or_insert<'v, 'a>(&self, value: &'v mut Value<'a>) -> &'v mut Value<'a> {
+        if value.is_null() {
+            *value = Value::Object(Map::new());
+        }
+
+        match value {
+            Value::Object(properties) => {
+                if !properties.contains_key(self) {
+                    properties.insert(Cow::Owned(self.to_string()), Value::Null);
+                }
+
+                // inserted above if it was missing
+                properties.get_mut(self).unwrap()
+            }
+            _ => panic!("cannot index into a non object value with '{}'", self),
+        }
+    }
+}
+
+impl ValueIndex for String {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        self.as_str().index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        self.as_str().index_into_mut(value)
+    }
+
+    fn index_or_insert<'v, 'a>(&self, value: &'v mut Value<'a>) -> &'v mut Value<'a> {
+        self.as_str().index_or_insert(value)
+    }
+}
+
+impl<T: ValueIndex + ?Sized> ValueIndex for &T {
+    fn index_into<'v, 'a>(&self, value: &'v Value<'a>) -> Option<&'v Value<'a>> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v, 'a>(&self, value: &'v mut Value<'a>) -> Option<&'v mut Value<'a>> {
+        (**self).index_into_mut(value)
+    }
+
+    fn index_or_insert<'v, 'a>(&self, value: &'v mut Value<'a>) -> &'v mut Value<'a> {
+        (**self).index_or_insert(value)
+    }
+}
+
+/// A step from a value into one of its children.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Writes a path from the top level value like `$.servers[1].port`, keys that aren't
+/// identifiers are written as `$['a b']`.
+pub(crate) struct PathDisplay<'p>(pub(crate) &'p [Segment]);
+
+impl fmt::Display for PathDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+
+        for segment in self.0 {
+            match segment {
+                Segment::Key(key) if is_plain_key(key) => write!(f, ".{}", key)?,
+                Segment::Key(key) => {
+                    write!(f, "['")?;
+
+                    for c in key.chars() {
+                        match c {
+                            '\'' => write!(f, "\\'")?,
+                            '\\' => write!(f, "\\\\")?,
+                            c => write!(f, "{}", c.escape_debug())?,
+                        }
+                    }
+
+                    write!(f, "']")?;
+                }
+                Segment::Index(i) => write!(f, "[{}]", i)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_plain_key(key: &str) -> bool {
+    let mut chars = key.chars();
+
+    chars.next().is_some_and(|c| is_identifier_start(&c)) && chars.all(|c| is_identifier_part(&c))
+}
+
+/// Indexing a missing key or position, or something that isn't an object or array,
+/// returns [`Value::Null`] instead of panicking.
+///
+/// ```text
+/// let name = value["users"][0]["name"].as_str();
+/// ```
+impl<'a, I: ValueIndex> Index<I> for Value<'a> {
+    type Output = Value<'a>;
+
+    fn index(&self, index: I) -> &Value<'a> {
+        index.index_into(self).unwrap_or(&NULL)
+    }
+}
+
+/// Inserts missing keys into objects, `null` is turned into an object when indexed by key.
+/// Panics for positions out of bounds and values that can't be indexed.
+impl<I: ValueIndex> IndexMut<I> for Value<'_> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        index.index_or_insert(self)
+    }
+}
+
+impl PartialEq<str> for Value<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
+}
+
+impl PartialEq<&str> for Value<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == Some(*other)
+    }
+}
+
+impl PartialEq<String> for Value<'_> {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == Some(other.as_str())
+    }
+}
+
+impl PartialEq<bool> for Value<'_> {
+    fn eq(&self, other: &bool) -> bool {
+        self.as_bool() == Some(*other)
+    }
+}
+
+macro_rules! eq_integer {
+    ($($typ:ty)*) => {
+        $(
+            impl PartialEq<$typ> for Value<'_> {
+                fn eq(&self, other: &$typ) -> bool {
+                    self.as_number().and_then(|num| num.to_i128().ok()) == Some(*other as i128)
+                }
+            }
+        )*
+    };
+}
+
+eq_integer! { u8 u16 u32 u64 usize i8 i16 i32 i64 isize }
+
+impl PartialEq<f64> for Value<'_> {
+    fn eq(&self, other: &f64) -> bool {
+        self.as_f64() == Some(*other)
+    }
+}
+
+impl From<bool> for Value<'_> {
+    fn from(bool: bool) -> Self {
+        Value::Bool(bool)
+    }
+}
+
+impl From<Number> for Value<'_> {
+    fn from(num: Number) -> Self {
+        Value::Number(num)
+    }
+}
+
+macro_rules! from_integer {
+    ($($typ:ty)*) => {
+        $(
+            impl From<$typ> for Value<'_> {
+                fn from(n: $typ) -> Self {
+                    Value::Number(Number::from(n))
+                }
+            }
+        )*
+    };
+}
+
+from_integer! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize }
+
+/// `NaN` and infinities can't be represented in JSON and become [`Value::Null`].
+impl From<f64> for Value<'_> {
+    fn from(f: f64) -> Self {
+        Number::from_f64(f).map_or(Value::Null, Value::Number)
+    }
+}
+
+/// `NaN` and infinities can't be represented in JSON and become [`Value::Null`].
+impl From<f32> for Value<'_> {
+    fn from(f: f32) -> Self {
+        Value::from(f as f64)
+    }
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(str: &'a str) -> Self {
+        Value::String(Cow::Borrowed(str))
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(str: String) -> Self {
+        Value::String(Cow::Owned(str))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Value<'a> {
+    fn from(str: Cow<'a, str>) -> Self {
+        Value::String(str)
+    }
+}
+
+impl From<()> for Value<'_> {
+    fn from(_: ()) -> Self {
+        Value::Null
+    }
+}
+
+impl<'a, T: Into<Value<'a>>> From<Option<T>> for Value<'a> {
+    fn from(option: Option<T>) -> Self {
+        option.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<'a, T: Into<Value<'a>>> From<Vec<T>> for Value<'a> {
+    fn from(values: Vec<T>) -> Self {
+        Value::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<'a, T: Clone + Into<Value<'a>>> From<&[T]> for Value<'a> {
+    fn from(values: &[T]) -> Self {
+        Value::Array(values.iter().cloned().map(Into::into).collect())
+    }
+}
+
+impl<'a> From<Map<Cow<'a, str>, Value<'a>>> for Value<'a> {
+    fn from(properties: Map<Cow<'a, str>, Value<'a>>) -> Self {
+        Value::Object(properties)
+    }
+}
+
+impl<'a, T: Into<Value<'a>>> FromIterator<T> for Value<'a> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Value::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<'a, K: Into<Cow<'a, str>>, V: Into<Value<'a>>> FromIterator<(K, V)> for Value<'a> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Value::Object(
+            iter.into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
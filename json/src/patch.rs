@@ -0,0 +1,635 @@
+//! JSON Patch (RFC 6902) and JSON Merge Patch (RFC 7396).
+//!
+//! A patch is a list of operations applied in order. Applying is atomic, when any
+//! operation fails, including a `test`, the value is left as it was.
+//!
+//! A merge patch is a value that looks like the result, see [`merge_patch`].
+//!
+//! ```text
+//! let patch = Patch::parse(r#"[
+//!     { "op": "test", "path": "/version", "value": 1 },
+//!     { "op": "replace", "path": "/version", "value": 2 },
+//!     { "op": "move", "from": "/name", "path": "/title" }
+//! ]"#)?;
+//!
+//! patch.apply(&mut value)?;
+//!
+//! // and back again
+//! let undo = Patch::diff(&value, &original);
+//! ```
+
+use std::{borrow::Cow, fmt};
+
+use thiserror::Error;
+
+use crate::{
+    parser::{error::Error, map::Map, Parser, Value},
+    pointer::{JsonPointer, PointerError},
+};
+
+#[derive(Debug, Error)]
+pub enum PatchError {
+    /// The patch isn't valid JSON
+    #[error(transparent)]
+    Parse(#[from] Error),
+    /// The patch isn't an array of operations
+    #[error("Patch Error: A patch must be an array of operations, found {0}.")]
+    NotArray(&'static str),
+    /// An operation is missing a member or has one of the wrong type
+    #[error("Patch Error: Operation {index}: {message}")]
+    Invalid { index: usize, message: String },
+    /// A pointer of the operation is invalid or doesn't lead to a value
+    #[error("Patch Error: Operation {index} ({op} {path}): {source}")]
+    Pointer {
+        index: usize,
+        op: &'static str,
+        /// The pointer that failed, the `path` of the operation or its `from`
+        path: String,
+        source: PointerError,
+    },
+    /// A `test` operation found a different value
+    #[error("Patch Error: Operation {index} (test {path}): Expected {expected}, found {found}.")]
+    TestFailed {
+        index: usize,
+        path: String,
+        expected: String,
+        found: String,
+    },
+    /// A `move` operation would move a value into itself
+    #[error("Patch Error: Operation {index} (move {path}): '{from}' can't be moved into one of its children.")]
+    MoveIntoChild {
+        index: usize,
+        from: String,
+        path: String,
+    },
+}
+
+/// A single operation of a [`Patch`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation<'a> {
+    /// Adds a property, inserts into an array (`-` appends) or replaces the root
+    Add {
+        path: JsonPointer,
+        value: Value<'a>,
+    },
+    Remove {
+        path: JsonPointer,
+    },
+    /// Replaces a value that has to exist
+    Replace {
+        path: JsonPointer,
+        value: Value<'a>,
+    },
+    Move {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    Copy {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    /// Fails unless the value at `path` is equal to `value`
+    Test {
+        path: JsonPointer,
+        value: Value<'a>,
+    },
+}
+
+impl<'a> Operation<'a> {
+    /// The name of the operation as written in the `op` member.
+    pub fn op(&self) -> &'static str {
+        match self {
+            Operation::Add { .. } => "add",
+            Operation::Remove { .. } => "remove",
+            Operation::Replace { .. } => "replace",
+            Operation::Move { .. } => "move",
+            Operation::Copy { .. } => "copy",
+            Operation::Test { .. } => "test",
+        }
+    }
+
+    pub fn path(&self) -> &JsonPointer {
+        match self {
+            Operation::Add { path, .. }
+            | Operation::Remove { path }
+            | Operation::Replace { path, .. }
+            | Operation::Move { path, .. }
+            | Operation::Copy { path, .. }
+            | Operation::Test { path, .. } => path,
+        }
+    }
+
+    /// Reads an operation object, `index` is its position in the patch for errors.
+    fn from_value(index: usize, value: Value<'a>) -> Result<Self, PatchError> {
+        let Value::Object(mut members) = value else {
+            return Err(PatchError::Invalid {
+                index,
+                message: format!("Expected an object, found {}.", value.kind()),
+            });
+        };
+
+        let string = |name: &str| match members.get(name) {
+            Some(Value::String(str)) => Ok(str.to_string()),
+            Some(other) => Err(PatchError::Invalid {
+                index,
+                message: format!("'{}' must be a string, found {}.", name, other.kind()),
+            }),
+            None => Err(PatchError::Invalid {
+                index,
+                message: format!("Missing '{}'.", name),
+            }),
+        };
+
+        let op = string("op")?;
+
+        let op = match op.as_str() {
+            "add" => "add",
+            "remove" => "remove",
+            "replace" => "replace",
+            "move" => "move",
+            "copy" => "copy",
+            "test" => "test",
+            _ => {
+                return Err(PatchError::Invalid {
+                    index,
+                    message: format!("Unknown operation '{}'.", op),
+                })
+            }
+        };
+
+        let path = string("path")?;
+
+        let pointer = |pointer: &str| {
+            JsonPointer::parse(pointer).map_err(|source| PatchError::Pointer {
+                index,
+                op,
+                path: pointer.to_string(),
+                source,
+            })
+        };
+
+        let from = match op {
+            "move" | "copy" => Some(pointer(&string("from")?)?),
+            _ => None,
+        };
+
+        let path = pointer(&path)?;
+
+        // `null` is a valid value so only a missing member is an error
+        let value = match op {
+            "add" | "replace" | "test" => {
+                members.remove("value").ok_or_else(|| PatchError::Invalid {
+                    index,
+                    message: "Missing 'value'.".to_string(),
+                })?
+            }
+            _ => Value::Null,
+        };
+
+        Ok(match op {
+            "add" => Operation::Add { path, value },
+            "remove" => Operation::Remove { path },
+            "replace" => Operation::Replace { path, value },
+            "move" => Operation::Move {
+                from: from.unwrap(),
+                path,
+            },
+            "copy" => Operation::Copy {
+                from: from.unwrap(),
+                path,
+            },
+            _ => Operation::Test { path, value },
+        })
+    }
+
+    fn to_value(&self) -> Value<'a> {
+        let mut members = Map::new();
+
+        members.insert(Cow::Borrowed("op"), Value::from(self.op()));
+
+        if let Operation::Move { from, .. } | Operation::Copy { from, .. } = self {
+            members.insert(Cow::Borrowed("from"), Value::from(from.to_string()));
+        }
+
+        members.insert(Cow::Borrowed("path"), Value::from(self.path().to_string()));
+
+        if let Operation::Add { value, .. }
+        | Operation::Replace { value, .. }
+        | Operation::Test { value, .. } = self
+        {
+            members.insert(Cow::Borrowed("value"), value.clone());
+        }
+
+        Value::Object(members)
+    }
+
+    fn apply(&self, index: usize, target: &mut Value<'a>) -> Result<(), PatchError> {
+        let pointer_error = |pointer: &JsonPointer, source| PatchError::Pointer {
+            index,
+            op: self.op(),
+            path: pointer.to_string(),
+            source,
+        };
+
+        match self {
+            Operation::Add { path, value } => {
+                path.insert(target, value.clone())
+                    .map_err(|err| pointer_error(path, err))?;
+            }
+            Operation::Remove { path } => {
+                path.remove(target)
+                    .map_err(|err| pointer_error(path, err))?;
+            }
+            Operation::Replace { path, value } => {
+                *path
+                    .get_mut(target)
+                    .map_err(|err| pointer_error(path, err))? = value.clone();
+            }
+            Operation::Move { from, path } => {
+                if from == path {
+                    // still has to exist
+                    from.get(target).map_err(|err| pointer_error(from, err))?;
+
+                    return Ok(());
+                }
+
+                if path.tokens().starts_with(from.tokens()) {
+                    return Err(PatchError::MoveIntoChild {
+                        index,
+                        from: from.to_string(),
+                        path: path.to_string(),
+                    });
+                }
+
+                let value = from
+                    .remove(target)
+                    .map_err(|err| pointer_error(from, err))?;
+
+                path.insert(target, value)
+                    .map_err(|err| pointer_error(path, err))?;
+            }
+            Operation::Copy { from, path } => {
+                let value = from
+                    .get(target)
+                    .map_err(|err| pointer_error(from, err))?
+                    .clone();
+
+                path.insert(target, value)
+                    .map_err(|err| pointer_error(path, err))?;
+            }
+            Operation::Test { path, value } => {
+                let found = path.get(target).map_err(|err| pointer_error(path, err))?;
+
+                if !found.json_eq(value) {
+                    return Err(PatchError::TestFailed {
+                        index,
+                        path: path.to_string(),
+                        expected: value.to_string(),
+                        found: found.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A list of [`Operation`]s.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Patch<'a> {
+    pub operations: Vec<Operation<'a>>,
+}
+
+impl<'a> Patch<'a> {
+    pub fn new(operations: Vec<Operation<'a>>) -> Self {
+        Self { operations }
+    }
+
+    /// Parses the JSON text of a patch. Values in the operations borrow from `source`
+    /// where possible.
+    pub fn parse(source: &'a str) -> Result<Self, PatchError> {
+        let value = Parser::new(Default::default()).parse(source)?;
+
+        Self::from_value(value)
+    }
+
+    /// Reads a patch from an array of operation objects.
+    pub fn from_value(value: Value<'a>) -> Result<Self, PatchError> {
+        let Value::Array(values) = value else {
+            return Err(PatchError::NotArray(value.kind()));
+        };
+
+        let operations = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| Operation::from_value(index, value))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { operations })
+    }
+
+    /// The patch as an array of operation objects.
+    pub fn to_value(&self) -> Value<'a> {
+        Value::Array(self.operations.iter().map(Operation::to_value).collect())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Applies every operation in order. When one fails `value` is left unchanged and
+    /// the error has the index of the operation.
+    pub fn apply(&self, value: &mut Value<'a>) -> Result<(), PatchError> {
+        // work on a copy so a failure halfway doesn't leave half of the patch applied
+        let mut patched = value.clone();
+
+        for (index, operation) in self.operations.iter().enumerate() {
+            operation.apply(index, &mut patched)?;
+        }
+
+        *value = patched;
+
+        Ok(())
+    }
+
+    /// Creates a patch that turns `from` into `to`.
+    ///
+    /// Objects are compared key by key and arrays position by position, elements are
+    /// added or removed at the end. Anything else that differs is replaced.
+    pub fn diff(from: &Value<'a>, to: &Value<'a>) -> Self {
+        let mut patch = Self::default();
+
+        diff(&JsonPointer::root(), from, to, &mut patch.operations);
+
+        patch
+    }
+}
+
+/// Writes the patch as compact JSON.
+impl fmt::Display for Patch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_value().fmt(f)
+    }
+}
+
+fn diff<'a>(
+    path: &JsonPointer,
+    from: &Value<'a>,
+    to: &Value<'a>,
+    operations: &mut Vec<Operation<'a>>,
+) {
+    if from.json_eq(to) {
+        return;
+    }
+
+    let child = |path: &JsonPointer, token: String| {
+        let mut child = path.clone();
+
+        child.push(token);
+        child
+    };
+
+    match (from, to) {
+        (Value::Object(from), Value::Object(to)) => {
+            for (key, value) in from {
+                match to.get(key.as_ref()) {
+                    Some(other) => diff(&child(path, key.to_string()), value, other, operations),
+                    None => operations.push(Operation::Remove {
+                        path: child(path, key.to_string()),
+                    }),
+                }
+            }
+
+            for (key, value) in to {
+                if !from.contains_key(key.as_ref()) {
+                    operations.push(Operation::Add {
+                        path: child(path, key.to_string()),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => {
+            for (i, (value, other)) in from.iter().zip(to).enumerate() {
+                diff(&child(path, i.to_string()), value, other, operations);
+            }
+
+            // from the end so the earlier indices stay valid
+            for i in (to.len()..from.len()).rev() {
+                operations.push(Operation::Remove {
+                    path: child(path, i.to_string()),
+                });
+            }
+
+            for (i, value) in to.iter().enumerate().skip(from.len()) {
+                operations.push(Operation::Add {
+                    path: child(path, i.to_string()),
+                    value: value.clone(),
+                });
+            }
+        }
+        _ => operations.push(Operation::Replace {
+            path: path.clone(),
+            value: to.clone(),
+        }),
+    }
+}
+
+/// Applies an RFC 7396 merge patch to `target`.
+///
+/// An object patch is merged into the target key by key: `null` removes the key and
+/// any other value is merged into the target's value recursively. Anything but an
+/// object replaces the target, arrays included. Keys already in the target keep their
+/// place, new ones go where the target's [`KeyOrder`](crate::parser::map::KeyOrder)
+/// puts them.
+pub fn merge_patch<'a>(target: &mut Value<'a>, patch: &Value<'a>) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::with_order(patch.order()));
+    }
+
+    let Value::Object(properties) = target else {
+        unreachable!()
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            properties.remove(key.as_ref());
+
+            continue;
+        }
+
+        match properties.get_mut(key.as_ref()) {
+            Some(existing) => merge_patch(existing, value),
+            None => {
+                // merging into nothing drops the nulls of nested objects
+                let mut new = Value::Null;
+
+                merge_patch(&mut new, value);
+                properties.insert(key.clone(), new);
+            }
+        }
+    }
+}
+
+/// Creates the merge patch that turns `from` into `to`.
+///
+/// Objects are compared key by key, with `null` for the removed keys. Merge patches
+/// can't set a property to `null` or change part of an array, so `null`s inside
+/// objects of `to` are left out and a changed array is replaced as a whole.
+pub fn diff_as_merge_patch<'a>(from: &Value<'a>, to: &Value<'a>) -> Value<'a> {
+    let Value::Object(to) = to else {
+        return to.clone();
+    };
+
+    // an object patch turns anything else into an empty object first
+    let empty = Map::new();
+
+    let from = match from {
+        Value::Object(from) => from,
+        _ => &empty,
+    };
+
+    let mut patch = Map::with_order(to.order());
+
+    for (key, value) in to {
+        match from.get(key.as_ref()) {
+            Some(old) if old.json_eq(value) => {}
+            Some(old) => {
+                patch.insert(key.clone(), diff_as_merge_patch(old, value));
+            }
+            None if value.is_null() => {}
+            None => {
+                patch.insert(key.clone(), diff_as_merge_patch(&Value::Null, value));
+            }
+        }
+    }
+
+    for key in from.keys() {
+        if !to.contains_key(key.as_ref()) {
+            patch.insert(key.clone(), Value::Null);
+        }
+    }
+
+    Value::Object(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn failed_test_leaves_the_value_unchanged() {
+        let mut value = json!({ "version": 1, "name": "a" });
+        let original = value.clone();
+
+        let patch = Patch::parse(
+            r#"[
+                { "op": "replace", "path": "/version", "value": 2 },
+                { "op": "remove", "path": "/name" },
+                { "op": "test", "path": "/version", "value": 1 }
+            ]"#,
+        )
+        .unwrap();
+
+        let err = patch.apply(&mut value).unwrap_err();
+
+        assert!(
+            matches!(err, PatchError::TestFailed { index: 2, .. }),
+            "{}",
+            err
+        );
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn move_into_a_child_is_rejected() {
+        let mut value = json!({ "a": { "b": {} } });
+        let original = value.clone();
+
+        let patch = Patch::parse(r#"[{ "op": "move", "from": "/a", "path": "/a/b/c" }]"#).unwrap();
+
+        let err = patch.apply(&mut value).unwrap_err();
+
+        assert!(
+            matches!(err, PatchError::MoveIntoChild { index: 0, .. }),
+            "{}",
+            err
+        );
+        assert_eq!(value, original);
+
+        // a sibling that only shares a prefix isn't a child
+        let mut value = json!({ "a": 1 });
+
+        Patch::parse(r#"[{ "op": "move", "from": "/a", "path": "/ab" }]"#)
+            .unwrap()
+            .apply(&mut value)
+            .unwrap();
+
+        assert_eq!(value, json!({ "ab": 1 }));
+    }
+
+    #[test]
+    fn invalid_from_is_reported() {
+        let err = Patch::parse(r#"[{ "op": "move", "from": "bad", "path": "/x" }]"#).unwrap_err();
+
+        assert!(
+            matches!(&err, PatchError::Pointer { path, .. } if path == "bad"),
+            "{}",
+            err
+        );
+
+        let mut value = json!({});
+
+        let err = Patch::parse(r#"[{ "op": "copy", "from": "/missing", "path": "/x" }]"#)
+            .unwrap()
+            .apply(&mut value)
+            .unwrap_err();
+
+        assert!(
+            matches!(&err, PatchError::Pointer { path, .. } if path == "/missing"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn diff_round_trip() {
+        let from = json!({
+            "name": "a",
+            "tags": ["x", "y", "z"],
+            "nested": { "keep": true, "drop": null },
+            "a/b": 1
+        });
+        let to = json!({
+            "name": "b",
+            "tags": ["x", "z"],
+            "nested": { "keep": true, "new": [1, 2] },
+            "a~b": 1,
+            "added": null
+        });
+
+        for (from, to) in [(&from, &to), (&to, &from)] {
+            let patch = Patch::diff(from, to);
+
+            // through text to check the operations can be read back
+            let text = patch.to_string();
+            let patch = Patch::parse(&text).unwrap();
+
+            let mut value = from.clone();
+
+            patch.apply(&mut value).unwrap();
+
+            assert_eq!(&value, to, "{}", text);
+        }
+
+        assert!(Patch::diff(&from, &from).is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use regex::Regex;
+
+use crate::parser::Value;
+
+/// `$` or `@` followed by segments.
+#[derive(Debug, Clone)]
+pub(crate) struct Query {
+    /// Starts at `@` instead of `$`
+    pub(crate) current: bool,
+    pub(crate) segments: Vec<QuerySegment>,
+}
+
+impl Query {
+    /// Selects at most one node, which is what comparisons and value arguments need.
+    pub(crate) fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            !segment.descendant
+                && matches!(
+                    segment.selectors.as_slice(),
+                    [Selector::Name(_) | Selector::Index(_)]
+                )
+        })
+    }
+}
+
+/// `[selectors]`, `.name` and `.*` or their `..` descendant versions.
+#[derive(Debug, Clone)]
+pub(crate) struct QuerySegment {
+    pub(crate) descendant: bool,
+    pub(crate) selectors: Vec<Selector>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Logical),
+}
+
+/// An expression of a filter that is either true or false.
+#[derive(Debug, Clone)]
+pub(crate) enum Logical {
+    Or(Vec<Logical>),
+    And(Vec<Logical>),
+    Not(Box<Logical>),
+    Compare(Box<Comparable>, Op, Box<Comparable>),
+    /// True when the query selects anything
+    Exists(Query),
+    /// A function returning a logical value or nodes
+    Function(FunctionCall),
+}
+
+/// Something that evaluates to a single value or nothing.
+#[derive(Debug, Clone)]
+pub(crate) enum Comparable {
+    Literal(Value<'static>),
+    /// Always singular
+    Query(Query),
+    /// Always returns a value
+    Function(FunctionCall),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// The types of the function extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Type {
+    /// A single value or nothing
+    Value,
+    Logical,
+    Nodes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Function {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+impl Function {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "length" => Self::Length,
+            "count" => Self::Count,
+            "match" => Self::Match,
+            "search" => Self::Search,
+            "value" => Self::Value,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Length => "length",
+            Self::Count => "count",
+            Self::Match => "match",
+            Self::Search => "search",
+            Self::Value => "value",
+        }
+    }
+
+    pub(crate) fn params(&self) -> &'static [Type] {
+        match self {
+            Self::Length => &[Type::Value],
+            Self::Count | Self::Value => &[Type::Nodes],
+            Self::Match | Self::Search => &[Type::Value, Type::Value],
+        }
+    }
+
+    pub(crate) fn result(&self) -> Type {
+        match self {
+            Self::Length | Self::Count | Self::Value => Type::Value,
+            Self::Match | Self::Search => Type::Logical,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FunctionCall {
+    pub(crate) function: Function,
+    pub(crate) args: Vec<Argument>,
+    /// The regex of `match` and `search` when the pattern is a string literal, `None` in
+    /// the inner option if the pattern isn't valid so it never matches
+    pub(crate) regex: Option<Option<Regex>>,
+}
+
+/// An argument already checked against the type of the parameter.
+#[derive(Debug, Clone)]
+pub(crate) enum Argument {
+    Value(Comparable),
+    Logical(Logical),
+    Nodes(Query),
+}
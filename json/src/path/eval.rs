@@ -0,0 +1,371 @@
+use std::{borrow::Cow, cmp::Ordering};
+
+use regex::Regex;
+
+use super::ast::{
+    Argument, Comparable, Function, FunctionCall, Logical, Op, Query, QuerySegment, Selector,
+};
+use crate::parser::Value;
+
+/// A node a query is evaluated on, with or without the path to it.
+pub(crate) trait Node<'v, 'a>: Clone {
+    fn value(&self) -> &'v Value<'a>;
+
+    fn key(&self, key: &str, value: &'v Value<'a>) -> Self;
+
+    fn index(&self, index: usize, value: &'v Value<'a>) -> Self;
+}
+
+/// Filters only need the values.
+impl<'v, 'a> Node<'v, 'a> for &'v Value<'a> {
+    fn value(&self) -> &'v Value<'a> {
+        self
+    }
+
+    fn key(&self, _: &str, value: &'v Value<'a>) -> Self {
+        value
+    }
+
+    fn index(&self, _: usize, value: &'v Value<'a>) -> Self {
+        value
+    }
+}
+
+/// Applies the segments of `query` one after the other starting at `start`.
+pub(crate) fn query<'v, 'a, N: Node<'v, 'a>>(
+    query: &Query,
+    start: N,
+    root: &'v Value<'a>,
+) -> Vec<N> {
+    let mut nodes = vec![start];
+
+    for segment in &query.segments {
+        let mut selected = vec![];
+
+        for node in nodes {
+            if segment.descendant {
+                for node in descendants(node) {
+                    select(segment, &node, root, &mut selected);
+                }
+            } else {
+                select(segment, &node, root, &mut selected);
+            }
+        }
+
+        nodes = selected;
+    }
+
+    nodes
+}
+
+/// The node followed by every node below it in document order.
+fn descendants<'v, 'a: 'v, N: Node<'v, 'a>>(node: N) -> Vec<N> {
+    let mut result = vec![];
+    let mut stack = vec![node];
+
+    while let Some(node) = stack.pop() {
+        // pushed in reverse so the first child is visited next
+        match node.value() {
+            Value::Array(values) => {
+                for (i, value) in values.iter().enumerate().rev() {
+                    stack.push(node.index(i, value));
+                }
+            }
+            Value::Object(properties) => {
+                for (key, value) in properties.iter().rev() {
+                    stack.push(node.key(key, value));
+                }
+            }
+            _ => {}
+        }
+
+        result.push(node);
+    }
+
+    result
+}
+
+fn select<'v, 'a, N: Node<'v, 'a>>(
+    segment: &QuerySegment,
+    node: &N,
+    root: &'v Value<'a>,
+    result: &mut Vec<N>,
+) {
+    for selector in &segment.selectors {
+        match (selector, node.value()) {
+            (Selector::Name(name), Value::Object(properties)) => {
+                if let Some(value) = properties.get(name.as_str()) {
+                    result.push(node.key(name, value));
+                }
+            }
+            (Selector::Wildcard, Value::Array(values)) => {
+                for (i, value) in values.iter().enumerate() {
+                    result.push(node.index(i, value));
+                }
+            }
+            (Selector::Wildcard, Value::Object(properties)) => {
+                for (key, value) in properties {
+                    result.push(node.key(key, value));
+                }
+            }
+            (Selector::Index(index), Value::Array(values)) => {
+                let index = normalize(*index, values.len());
+
+                if (0..values.len() as i64).contains(&index) {
+                    result.push(node.index(index as usize, &values[index as usize]));
+                }
+            }
+            (Selector::Slice { start, end, step }, Value::Array(values)) => {
+                for i in slice(*start, *end, step.unwrap_or(1), values.len()) {
+                    result.push(node.index(i, &values[i]));
+                }
+            }
+            (Selector::Filter(filter), Value::Array(values)) => {
+                for (i, value) in values.iter().enumerate() {
+                    if test(filter, value, root) {
+                        result.push(node.index(i, value));
+                    }
+                }
+            }
+            (Selector::Filter(filter), Value::Object(properties)) => {
+                for (key, value) in properties {
+                    if test(filter, value, root) {
+                        result.push(node.key(key, value));
+                    }
+                }
+            }
+            // selectors select nothing from values they don't apply to
+            _ => {}
+        }
+    }
+}
+
+/// Counts negative indices from the end.
+fn normalize(index: i64, len: usize) -> i64 {
+    if index < 0 {
+        len as i64 + index
+    } else {
+        index
+    }
+}
+
+/// The positions selected by `start:end:step` in the order they are selected.
+fn slice(start: Option<i64>, end: Option<i64>, step: i64, len: usize) -> Vec<usize> {
+    let len = len as i64;
+    let mut result = vec![];
+
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0), len as usize).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len), len as usize).clamp(0, len);
+
+        let mut i = lower;
+
+        while i < upper {
+            result.push(i as usize);
+            i += step;
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1), len as usize).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1), len as usize).clamp(-1, len - 1);
+
+        let mut i = upper;
+
+        while lower < i {
+            result.push(i as usize);
+            i += step;
+        }
+    }
+
+    result
+}
+
+/// Evaluates a filter for the node `current`.
+fn test<'v, 'a>(logical: &Logical, current: &'v Value<'a>, root: &'v Value<'a>) -> bool {
+    match logical {
+        Logical::Or(terms) => terms.iter().any(|term| test(term, current, root)),
+        Logical::And(terms) => terms.iter().all(|term| test(term, current, root)),
+        Logical::Not(term) => !test(term, current, root),
+        Logical::Compare(left, op, right) => {
+            let left = evaluate(left, current, root);
+            let right = evaluate(right, current, root);
+
+            compare(left.as_deref(), *op, right.as_deref())
+        }
+        Logical::Exists(q) => !nodes(q, current, root).is_empty(),
+        Logical::Function(call) => match call_function(call, current, root) {
+            Output::Logical(result) => result,
+            Output::Nodes(nodes) => !nodes.is_empty(),
+            // the parser only allows functions returning a logical value or nodes here
+            Output::Value(_) => unreachable!(),
+        },
+    }
+}
+
+fn nodes<'v, 'a>(q: &Query, current: &'v Value<'a>, root: &'v Value<'a>) -> Vec<&'v Value<'a>> {
+    query(q, if q.current { current } else { root }, root)
+}
+
+/// The value of a comparable, `None` is nothing, which is different from `null`.
+fn evaluate<'v, 'a>(
+    comparable: &'v Comparable,
+    current: &'v Value<'a>,
+    root: &'v Value<'a>,
+) -> Option<Cow<'v, Value<'a>>> {
+    match comparable {
+        Comparable::Literal(value) => Some(Cow::Borrowed(value)),
+        // singular so there is at most one node
+        Comparable::Query(q) => nodes(q, current, root).pop().map(Cow::Borrowed),
+        Comparable::Function(call) => match call_function(call, current, root) {
+            Output::Value(value) => value,
+            // the parser only allows functions returning a value here
+            Output::Logical(_) | Output::Nodes(_) => unreachable!(),
+        },
+    }
+}
+
+/// What a function extension returns.
+enum Output<'v, 'a> {
+    Value(Option<Cow<'v, Value<'a>>>),
+    Logical(bool),
+    Nodes(Vec<&'v Value<'a>>),
+}
+
+fn call_function<'v, 'a>(
+    call: &'v FunctionCall,
+    current: &'v Value<'a>,
+    root: &'v Value<'a>,
+) -> Output<'v, 'a> {
+    let mut args = call.args.iter().map(|arg| match arg {
+        Argument::Value(comparable) => Output::Value(evaluate(comparable, current, root)),
+        Argument::Logical(logical) => Output::Logical(test(logical, current, root)),
+        Argument::Nodes(q) => Output::Nodes(nodes(q, current, root)),
+    });
+
+    // the parser checks the number and types of the arguments
+    let mut next = || args.next().unwrap();
+
+    match call.function {
+        Function::Length => {
+            let Output::Value(value) = next() else {
+                unreachable!()
+            };
+
+            let len = match value.as_deref() {
+                Some(Value::String(str)) => str.chars().count(),
+                Some(Value::Array(values)) => values.len(),
+                Some(Value::Object(properties)) => properties.len(),
+                _ => return Output::Value(None),
+            };
+
+            Output::Value(Some(Cow::Owned(Value::from(len))))
+        }
+        Function::Count => {
+            let Output::Nodes(nodes) = next() else {
+                unreachable!()
+            };
+
+            Output::Value(Some(Cow::Owned(Value::from(nodes.len()))))
+        }
+        Function::Value => {
+            let Output::Nodes(mut nodes) = next() else {
+                unreachable!()
+            };
+
+            if nodes.len() == 1 {
+                Output::Value(nodes.pop().map(Cow::Borrowed))
+            } else {
+                Output::Value(None)
+            }
+        }
+        Function::Match | Function::Search => {
+            let (Output::Value(value), Output::Value(pattern)) = (next(), next()) else {
+                unreachable!()
+            };
+
+            let (Some(Value::String(value)), Some(Value::String(pattern))) =
+                (value.as_deref(), pattern.as_deref())
+            else {
+                return Output::Logical(false);
+            };
+
+            let compiled;
+
+            let regex = match &call.regex {
+                Some(regex) => regex,
+                None => {
+                    compiled = regex(pattern, call.function == Function::Match);
+                    &compiled
+                }
+            };
+
+            Output::Logical(regex.as_ref().is_some_and(|regex| regex.is_match(value)))
+        }
+    }
+}
+
+/// Compiles an I-Regexp (RFC 9485) pattern, `whole` anchors it to the whole string for
+/// `match`. `None` if the pattern isn't valid.
+pub(crate) fn regex(pattern: &str, whole: bool) -> Option<Regex> {
+    let mut translated = String::new();
+    let mut chars = pattern.chars();
+    let mut in_class = false;
+
+    // `.` is any char but line breaks in I-Regexp, the regex crate only leaves out `\n`
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                translated.push(c);
+                translated.extend(chars.next());
+            }
+            '[' => {
+                in_class = true;
+                translated.push(c);
+            }
+            ']' => {
+                in_class = false;
+                translated.push(c);
+            }
+            '.' if !in_class => translated.push_str("[^\\n\\r]"),
+            c => translated.push(c),
+        }
+    }
+
+    let pattern = if whole {
+        format!("\\A(?:{})\\z", translated)
+    } else {
+        translated
+    };
+
+    Regex::new(&pattern).ok()
+}
+
+fn compare(left: Option<&Value>, op: Op, right: Option<&Value>) -> bool {
+    match op {
+        Op::Eq => equal(left, right),
+        Op::Ne => !equal(left, right),
+        Op::Lt => less(left, right),
+        Op::Le => less(left, right) || equal(left, right),
+        Op::Gt => less(right, left),
+        Op::Ge => less(right, left) || equal(left, right),
+    }
+}
+
+/// Nothing is only equal to nothing.
+fn equal(left: Option<&Value>, right: Option<&Value>) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(left), Some(right)) => left.json_eq(right),
+        _ => false,
+    }
+}
+
+/// Only numbers and strings are ordered, strings by their code points.
+fn less(left: Option<&Value>, right: Option<&Value>) -> bool {
+    match (left, right) {
+        (Some(Value::Number(left)), Some(Value::Number(right))) => {
+            left.compare(right) == Some(Ordering::Less)
+        }
+        (Some(Value::String(left)), Some(Value::String(right))) => left < right,
+        _ => false,
+    }
+}
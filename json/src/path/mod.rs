@@ -0,0 +1,300 @@
+//! JSONPath queries (RFC 9535).
+//!
+//! A query is compiled once with [`JsonPath::parse`] and can then be run against any
+//! number of values. Every selected node comes with its normalized path.
+//!
+//! ```text
+//! let path = JsonPath::parse("$.servers[?@.port > 8000 && match(@.host, 'web-.*')].host")?;
+//!
+//! for node in path.query(&value) {
+//!     println!("{}: {}", node.path, node.value);
+//! }
+//!
+//! // $['servers'][1]['host']: "web-2"
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{
+    parser::{position::Position, value::Segment, Value},
+    pointer::JsonPointer,
+};
+
+mod ast;
+mod eval;
+mod parser;
+
+/// The query isn't valid, this includes function extensions called with arguments of
+/// the wrong type.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("Path Error: {position}: {message}")]
+pub struct PathError {
+    pub message: String,
+    /// Where in the query, columns are counted in chars
+    pub position: Position,
+}
+
+/// A compiled JSONPath query.
+///
+/// Filters support comparisons, `&&`, `||`, `!` and the function extensions `length`,
+/// `count`, `match`, `search` and `value`. Regular expressions of `match` and `search`
+/// are compiled along with the query when they are string literals.
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    source: String,
+    query: ast::Query,
+}
+
+impl JsonPath {
+    pub fn parse(query: &str) -> Result<Self, PathError> {
+        Ok(Self {
+            source: query.to_string(),
+            query: parser::QueryParser::parse(query)?,
+        })
+    }
+
+    /// Returns every selected node in order along with its normalized path.
+    pub fn query<'v, 'a>(&self, value: &'v Value<'a>) -> Vec<Node<'v, 'a>> {
+        let root = Node {
+            path: NormalizedPath::default(),
+            value,
+        };
+
+        eval::query(&self.query, root, value)
+    }
+
+    /// Returns every selected value in order, skipping the work of building their paths.
+    pub fn query_values<'v, 'a>(&self, value: &'v Value<'a>) -> Vec<&'v Value<'a>> {
+        eval::query(&self.query, value, value)
+    }
+
+    /// The text the query was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = PathError;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        Self::parse(query)
+    }
+}
+
+/// A value selected by a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node<'v, 'a> {
+    pub path: NormalizedPath,
+    pub value: &'v Value<'a>,
+}
+
+impl<'v, 'a> eval::Node<'v, 'a> for Node<'v, 'a> {
+    fn value(&self) -> &'v Value<'a> {
+        self.value
+    }
+
+    fn key(&self, key: &str, value: &'v Value<'a>) -> Self {
+        let mut path = self.path.clone();
+
+        path.0.push(Segment::Key(key.to_string()));
+
+        Self { path, value }
+    }
+
+    fn index(&self, index: usize, value: &'v Value<'a>) -> Self {
+        let mut path = self.path.clone();
+
+        path.0.push(Segment::Index(index));
+
+        Self { path, value }
+    }
+}
+
+/// The unique path to a node written like `$['servers'][0]['host']`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NormalizedPath(Vec<Segment>);
+
+impl NormalizedPath {
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    /// The same location as a JSON Pointer.
+    pub fn to_pointer(&self) -> JsonPointer {
+        let mut pointer = JsonPointer::root();
+
+        for segment in &self.0 {
+            match segment {
+                Segment::Key(key) => pointer.push(key.as_str()),
+                Segment::Index(i) => pointer.push(i.to_string()),
+            }
+        }
+
+        pointer
+    }
+}
+
+impl fmt::Display for NormalizedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "$")?;
+
+        for segment in &self.0 {
+            match segment {
+                Segment::Key(key) => {
+                    write!(f, "['")?;
+
+                    for c in key.chars() {
+                        match c {
+                            '\u{8}' => write!(f, "\\b")?,
+                            '\u{c}' => write!(f, "\\f")?,
+                            '\n' => write!(f, "\\n")?,
+                            '\r' => write!(f, "\\r")?,
+                            '\t' => write!(f, "\\t")?,
+                            '\'' => write!(f, "\\'")?,
+                            '\\' => write!(f, "\\\\")?,
+                            c if c < '\u{20}' => write!(f, "\\u{:04x}", c as u32)?,
+                            c => write!(f, "{}", c)?,
+                        }
+                    }
+
+                    write!(f, "']")?;
+                }
+                Segment::Index(i) => write!(f, "[{}]", i)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn paths(query: &str, value: &Value<'_>) -> Vec<String> {
+        JsonPath::parse(query)
+            .unwrap()
+            .query(value)
+            .iter()
+            .map(|node| node.path.to_string())
+            .collect()
+    }
+
+    fn values<'v, 'a>(query: &str, value: &'v Value<'a>) -> Vec<&'v Value<'a>> {
+        JsonPath::parse(query).unwrap().query_values(value)
+    }
+
+    #[test]
+    fn normalized_paths() {
+        let value = json!({ "servers": [{ "host": "web-1" }, { "host": "web-2" }] });
+
+        assert_eq!(
+            paths("$..host", &value),
+            ["$['servers'][0]['host']", "$['servers'][1]['host']"]
+        );
+    }
+
+    #[test]
+    fn normalized_paths_escape_names() {
+        let value = json!({ "a'b": { "c\"d": 1, "e\\f": 2, "g\nh": 3 } });
+
+        assert_eq!(
+            paths("$.*.*", &value),
+            [
+                r#"$['a\'b']['c"d']"#,
+                r"$['a\'b']['e\\f']",
+                r"$['a\'b']['g\nh']",
+            ]
+        );
+    }
+
+    #[test]
+    fn slices_with_negative_steps() {
+        let value = json!([0, 1, 2, 3, 4, 5]);
+
+        assert_eq!(
+            values("$[::-1]", &value),
+            [
+                &json!(5),
+                &json!(4),
+                &json!(3),
+                &json!(2),
+                &json!(1),
+                &json!(0)
+            ]
+        );
+        assert_eq!(values("$[5:1:-2]", &value), [&json!(5), &json!(3)]);
+        assert_eq!(values("$[::-2]", &value), [&json!(5), &json!(3), &json!(1)]);
+        assert_eq!(values("$[-2::-3]", &value), [&json!(4), &json!(1)]);
+        assert!(values("$[::0]", &value).is_empty());
+    }
+
+    #[test]
+    fn non_singular_queries_are_rejected() {
+        for query in [
+            "$[?@.* == 1]",
+            "$[?@..a == 1]",
+            "$[?@[0:1] == 1]",
+            "$[?length(@.*) > 0]",
+        ] {
+            let err = JsonPath::parse(query).unwrap_err();
+
+            assert!(
+                err.message.contains("can select more than one value"),
+                "{}: {}",
+                query,
+                err
+            );
+        }
+
+        assert!(JsonPath::parse("$[?@.a == 1]").is_ok());
+        assert!(JsonPath::parse("$[?length(@.a) == 1]").is_ok());
+        assert!(JsonPath::parse("$[?count(@.*) == 1]").is_ok());
+    }
+
+    #[test]
+    fn integer_bounds() {
+        for query in [
+            "$[9007199254740991]",
+            "$[-9007199254740991]",
+            "$[1:9007199254740991:-9007199254740991]",
+        ] {
+            assert!(JsonPath::parse(query).is_ok(), "{}", query);
+        }
+
+        for query in [
+            "$[9007199254740992]",
+            "$[-9007199254740992]",
+            "$[1:9007199254740992]",
+            "$[::-9007199254740992]",
+        ] {
+            let err = JsonPath::parse(query).unwrap_err();
+
+            assert!(err.message.contains("out of range"), "{}: {}", query, err);
+        }
+    }
+
+    #[test]
+    fn match_is_anchored_and_search_is_not() {
+        let value = json!(["abc", "xabcx", "ab"]);
+
+        assert_eq!(values("$[?match(@, 'ab.')]", &value), [&json!("abc")]);
+        assert_eq!(
+            values("$[?search(@, 'ab.')]", &value),
+            [&json!("abc"), &json!("xabcx")]
+        );
+        assert!(values("$[?match(@, 'b.')]", &value).is_empty());
+        // the anchors apply to the whole alternation
+        assert_eq!(values("$[?match(@, 'a|xabcx')]", &value), [&json!("xabcx")]);
+    }
+}
@@ -0,0 +1,875 @@
+use std::borrow::Cow;
+
+use super::{
+    ast::{
+        Argument, Comparable, Function, FunctionCall, Logical, Op, Query, QuerySegment, Selector,
+        Type,
+    },
+    eval, PathError,
+};
+use crate::parser::{number::Number, position::Position, Value};
+
+/// Integers in index and slice selectors have to be exact in an IEEE 754 double.
+const MAX_INTEGER: i64 = (1 << 53) - 1;
+
+/// Parses the text of a query into a [`Query`], checking the types of function
+/// extensions as it goes.
+pub(crate) struct QueryParser<'s> {
+    source: &'s str,
+    /// Byte offset into `source`
+    current: usize,
+}
+
+/// A filter operand before it is known where it is used.
+enum Expression {
+    Operand(Comparable),
+    Logical(Logical),
+}
+
+impl<'s> QueryParser<'s> {
+    pub(crate) fn parse(source: &'s str) -> Result<Query, PathError> {
+        let mut parser = Self { source, current: 0 };
+
+        if !parser.mat("$") {
+            return Err(parser.error(
+                format!(
+                    "Expected '$' at the start of the query, found {}.",
+                    parser.found()
+                ),
+                0,
+            ));
+        }
+
+        let query = parser.query(false)?;
+
+        if !parser.is_at_end() {
+            return Err(parser.error(
+                format!("Unexpected {} in the query.", parser.found()),
+                parser.current,
+            ));
+        }
+
+        Ok(query)
+    }
+
+    /// Reads the segments after `$` or `@`.
+    fn query(&mut self, current: bool) -> Result<Query, PathError> {
+        let mut segments = vec![];
+
+        loop {
+            // blanks are only allowed between segments, not after the last one
+            let save = self.current;
+
+            self.skip_blank();
+
+            if !matches!(self.peek(), '.' | '[') {
+                self.current = save;
+                break;
+            }
+
+            segments.push(self.segment()?);
+        }
+
+        Ok(Query { current, segments })
+    }
+
+    fn segment(&mut self) -> Result<QuerySegment, PathError> {
+        if self.mat("..") {
+            let selectors = match self.peek() {
+                '[' => {
+                    self.advance();
+                    self.selectors()?
+                }
+                '*' => {
+                    self.advance();
+                    vec![Selector::Wildcard]
+                }
+                c if is_name_first(c) => vec![Selector::Name(self.name())],
+                _ => {
+                    return Err(self.error(
+                        format!(
+                            "Expected a name, '*' or '[' after '..', found {}.",
+                            self.found()
+                        ),
+                        self.current,
+                    ))
+                }
+            };
+
+            return Ok(QuerySegment {
+                descendant: true,
+                selectors,
+            });
+        }
+
+        let selectors = if self.mat(".") {
+            match self.peek() {
+                '*' => {
+                    self.advance();
+                    vec![Selector::Wildcard]
+                }
+                c if is_name_first(c) => vec![Selector::Name(self.name())],
+                _ => {
+                    return Err(self.error(
+                        format!("Expected a name or '*' after '.', found {}.", self.found()),
+                        self.current,
+                    ))
+                }
+            }
+        } else {
+            // go past '['
+            self.advance();
+            self.selectors()?
+        };
+
+        Ok(QuerySegment {
+            descendant: false,
+            selectors,
+        })
+    }
+
+    /// Reads the selectors of a bracketed selection after the `[`.
+    fn selectors(&mut self) -> Result<Vec<Selector>, PathError> {
+        let mut selectors = vec![];
+
+        loop {
+            self.skip_blank();
+
+            selectors.push(self.selector()?);
+
+            self.skip_blank();
+
+            if self.mat(",") {
+                continue;
+            }
+
+            if self.mat("]") {
+                return Ok(selectors);
+            }
+
+            return Err(self.error(
+                format!(
+                    "Expected ',' or ']' after a selector, found {}.",
+                    self.found()
+                ),
+                self.current,
+            ));
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, PathError> {
+        match self.peek() {
+            '\'' | '"' => Ok(Selector::Name(self.string()?)),
+            '*' => {
+                self.advance();
+
+                Ok(Selector::Wildcard)
+            }
+            '?' => {
+                self.advance();
+                self.skip_blank();
+
+                Ok(Selector::Filter(self.logical()?))
+            }
+            c if c == '-' || c == ':' || c.is_ascii_digit() => self.index_or_slice(),
+            _ => Err(self.error(
+                format!("Expected a selector, found {}.", self.found()),
+                self.current,
+            )),
+        }
+    }
+
+    /// `index` or `start:end:step` where every part of the slice is optional.
+    fn index_or_slice(&mut self) -> Result<Selector, PathError> {
+        let start = if self.peek() == ':' {
+            None
+        } else {
+            Some(self.integer()?)
+        };
+
+        self.skip_blank();
+
+        if !self.mat(":") {
+            // `start` is only missing when the selector starts with ':'
+            return Ok(Selector::Index(start.unwrap()));
+        }
+
+        self.skip_blank();
+
+        let end = self.optional_integer()?;
+
+        self.skip_blank();
+
+        let step = if self.mat(":") {
+            self.skip_blank();
+            self.optional_integer()?
+        } else {
+            None
+        };
+
+        Ok(Selector::Slice { start, end, step })
+    }
+
+    fn optional_integer(&mut self) -> Result<Option<i64>, PathError> {
+        if self.peek() == '-' || self.peek().is_ascii_digit() {
+            Ok(Some(self.integer()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, PathError> {
+        let start = self.current;
+
+        self.mat("-");
+
+        if !self.peek().is_ascii_digit() {
+            return Err(self.error(
+                format!("Expected a digit, found {}.", self.found()),
+                self.current,
+            ));
+        }
+
+        if self.mat("0") {
+            if self.peek().is_ascii_digit() {
+                return Err(self.error("Integers can't have leading zeros.", start));
+            }
+
+            if self.current - start == 2 {
+                return Err(self.error("'-0' isn't a valid integer.", start));
+            }
+        }
+
+        while self.peek().is_ascii_digit() {
+            self.advance();
+        }
+
+        let lexeme = &self.source[start..self.current];
+
+        match lexeme.parse::<i64>() {
+            Ok(n) if (-MAX_INTEGER..=MAX_INTEGER).contains(&n) => Ok(n),
+            _ => Err(self.error(
+                format!(
+                    "{} is out of range, integers have to be within ±(2^53 - 1).",
+                    lexeme
+                ),
+                start,
+            )),
+        }
+    }
+
+    /// A number literal in a filter, `-0` and fractions are allowed here.
+    fn number(&mut self) -> Result<Number, PathError> {
+        let start = self.current;
+
+        if self.mat("-") && !self.peek().is_ascii_digit() {
+            return Err(self.error("Expected digit after '-'.", self.current));
+        }
+
+        if self.mat("0") {
+            if self.peek().is_ascii_digit() {
+                return Err(self.error("Numbers can't have leading zeros.", start));
+            }
+        } else {
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+        }
+
+        let mut float = false;
+
+        if self.mat(".") {
+            if !self.peek().is_ascii_digit() {
+                return Err(self.error("Expected digit after '.'.", self.current));
+            }
+
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+
+            float = true;
+        }
+
+        if self.mat("e") || self.mat("E") {
+            if !self.mat("+") {
+                self.mat("-");
+            }
+
+            if !self.peek().is_ascii_digit() {
+                return Err(self.error("Expected digit in the exponent.", self.current));
+            }
+
+            while self.peek().is_ascii_digit() {
+                self.advance();
+            }
+
+            float = true;
+        }
+
+        let lexeme = &self.source[start..self.current];
+
+        let number = if float {
+            Number::from_float_lexeme(lexeme)
+        } else {
+            Number::from_integer_lexeme(lexeme)
+        };
+
+        number.ok_or_else(|| self.error(format!("{} is out of range.", lexeme), start))
+    }
+
+    /// A member name shorthand after `.` or `..`.
+    fn name(&mut self) -> String {
+        let start = self.current;
+
+        while is_name_char(self.peek()) {
+            self.advance();
+        }
+
+        self.source[start..self.current].to_string()
+    }
+
+    /// A single or double quoted string decoding its escape sequences.
+    fn string(&mut self) -> Result<String, PathError> {
+        let start = self.current;
+        let quote = self.advance();
+
+        let mut result = String::new();
+
+        loop {
+            if self.is_at_end() {
+                return Err(self.error(format!("Unclosed '{}'.", quote), start));
+            }
+
+            let c = self.advance();
+
+            match c {
+                c if c == quote => return Ok(result),
+                '\\' => self.escape(quote, &mut result)?,
+                c if c < '\u{20}' => {
+                    return Err(self.error(
+                        format!("Unescaped control character U+{:04X} in string.", c as u32),
+                        self.current - 1,
+                    ))
+                }
+                c => result.push(c),
+            }
+        }
+    }
+
+    /// Decodes the escape sequence after a `\` onto `result`.
+    fn escape(&mut self, quote: char, result: &mut String) -> Result<(), PathError> {
+        let start = self.current - 1;
+
+        if self.is_at_end() {
+            return Err(self.error("Unterminated escape sequence.", start));
+        }
+
+        match self.advance() {
+            'b' => result.push('\u{8}'),
+            'f' => result.push('\u{c}'),
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            '/' => result.push('/'),
+            '\\' => result.push('\\'),
+            // only the quote of the string can be escaped
+            c if c == quote => result.push(c),
+            'u' => {
+                let unit = self.hex_escape(start)?;
+
+                let code_point = match unit {
+                    0xD800..=0xDBFF => {
+                        // a high surrogate has to be followed by an escaped low surrogate
+                        if !self.mat("\\u") {
+                            return Err(self.error(
+                                format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                                start,
+                            ));
+                        }
+
+                        let low = self.hex_escape(start)?;
+
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(self.error(
+                                format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                                start,
+                            ));
+                        }
+
+                        0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => {
+                        return Err(self.error(
+                            format!("Lone surrogate '\\u{:04X}' in string.", unit),
+                            start,
+                        ))
+                    }
+                    _ => unit,
+                };
+
+                // surrogates have been handled above so this is always a valid scalar value
+                result.push(char::from_u32(code_point).unwrap());
+            }
+            c => {
+                return Err(self.error(
+                    format!("Invalid escape sequence '\\{}'.", c.escape_debug()),
+                    start,
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the 4 hex digits of a `\uXXXX` escape returning the UTF-16 code unit.
+    fn hex_escape(&mut self, start: usize) -> Result<u32, PathError> {
+        let mut unit = 0;
+
+        for _ in 0..4 {
+            let Some(digit) = self.peek().to_digit(16) else {
+                return Err(self.error("Expected 4 hex digits after '\\u'.", start));
+            };
+
+            self.advance();
+
+            unit = unit * 16 + digit;
+        }
+
+        Ok(unit)
+    }
+
+    /// `a || b || ...`
+    fn logical(&mut self) -> Result<Logical, PathError> {
+        let mut terms = vec![self.and()?];
+
+        loop {
+            let save = self.current;
+
+            self.skip_blank();
+
+            if !self.mat("||") {
+                self.current = save;
+                break;
+            }
+
+            self.skip_blank();
+
+            terms.push(self.and()?);
+        }
+
+        Ok(match terms.len() {
+            1 => terms.remove(0),
+            _ => Logical::Or(terms),
+        })
+    }
+
+    /// `a && b && ...`
+    fn and(&mut self) -> Result<Logical, PathError> {
+        let mut terms = vec![self.basic()?];
+
+        loop {
+            let save = self.current;
+
+            self.skip_blank();
+
+            if !self.mat("&&") {
+                self.current = save;
+                break;
+            }
+
+            self.skip_blank();
+
+            terms.push(self.basic()?);
+        }
+
+        Ok(match terms.len() {
+            1 => terms.remove(0),
+            _ => Logical::And(terms),
+        })
+    }
+
+    /// A parenthesized expression, a comparison or a test, the first and last can be negated.
+    fn basic(&mut self) -> Result<Logical, PathError> {
+        if self.mat("!") {
+            self.skip_blank();
+
+            if self.mat("(") {
+                return Ok(Logical::Not(Box::new(self.paren()?)));
+            }
+
+            let start = self.current;
+            let operand = self.operand()?;
+
+            return Ok(Logical::Not(Box::new(self.test(operand, start)?)));
+        }
+
+        if self.mat("(") {
+            return self.paren();
+        }
+
+        let start = self.current;
+        let left = self.operand()?;
+
+        let save = self.current;
+
+        self.skip_blank();
+
+        let Some(op) = self.comparison_op() else {
+            self.current = save;
+
+            return self.test(left, start);
+        };
+
+        self.skip_blank();
+
+        let right_start = self.current;
+        let right = self.operand()?;
+
+        Ok(Logical::Compare(
+            Box::new(self.comparable(left, start)?),
+            op,
+            Box::new(self.comparable(right, right_start)?),
+        ))
+    }
+
+    /// The rest of a parenthesized expression after the `(`.
+    fn paren(&mut self) -> Result<Logical, PathError> {
+        let start = self.current - 1;
+
+        self.skip_blank();
+
+        let expression = self.logical()?;
+
+        self.skip_blank();
+
+        if !self.mat(")") {
+            if self.is_at_end() {
+                return Err(self.error("Unclosed '('.", start));
+            }
+
+            return Err(self.error(
+                format!("Expected ')', found {}.", self.found()),
+                self.current,
+            ));
+        }
+
+        Ok(expression)
+    }
+
+    fn comparison_op(&mut self) -> Option<Op> {
+        let ops = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+
+        ops.into_iter()
+            .find(|(lexeme, _)| self.mat(lexeme))
+            .map(|(_, op)| op)
+    }
+
+    /// A literal, query or function call that isn't checked for where it is used yet.
+    fn operand(&mut self) -> Result<Comparable, PathError> {
+        let start = self.current;
+
+        match self.peek() {
+            '@' => {
+                self.advance();
+
+                Ok(Comparable::Query(self.query(true)?))
+            }
+            '$' => {
+                self.advance();
+
+                Ok(Comparable::Query(self.query(false)?))
+            }
+            '\'' | '"' => Ok(Comparable::Literal(Value::String(Cow::Owned(
+                self.string()?,
+            )))),
+            c if c == '-' || c.is_ascii_digit() => {
+                Ok(Comparable::Literal(Value::Number(self.number()?)))
+            }
+            c if c.is_ascii_lowercase() => {
+                while matches!(self.peek(), 'a'..='z' | '0'..='9' | '_') {
+                    self.advance();
+                }
+
+                let name = &self.source[start..self.current];
+
+                if self.peek() == '(' {
+                    return Ok(Comparable::Function(self.function(name, start)?));
+                }
+
+                match name {
+                    "true" => Ok(Comparable::Literal(Value::Bool(true))),
+                    "false" => Ok(Comparable::Literal(Value::Bool(false))),
+                    "null" => Ok(Comparable::Literal(Value::Null)),
+                    _ => Err(self.error(
+                        format!(
+                            "Unexpected '{}', expected a literal, query or function.",
+                            name
+                        ),
+                        start,
+                    )),
+                }
+            }
+            _ => Err(self.error(
+                format!(
+                    "Expected a literal, query or function, found {}.",
+                    self.found()
+                ),
+                start,
+            )),
+        }
+    }
+
+    /// The arguments of a function call starting at the `(`.
+    fn function(&mut self, name: &str, start: usize) -> Result<FunctionCall, PathError> {
+        let Some(function) = Function::from_name(name) else {
+            return Err(self.error(format!("Unknown function '{}'.", name), start));
+        };
+
+        let params = function.params();
+        let mut args = vec![];
+
+        // go past '('
+        self.advance();
+        self.skip_blank();
+
+        if !self.mat(")") {
+            loop {
+                let Some(&param) = params.get(args.len()) else {
+                    return Err(self.error(
+                        format!("{}() takes {}.", name, arguments(params.len())),
+                        self.current,
+                    ));
+                };
+
+                args.push(self.argument(function, param)?);
+
+                self.skip_blank();
+
+                if self.mat(",") {
+                    self.skip_blank();
+                    continue;
+                }
+
+                if self.mat(")") {
+                    break;
+                }
+
+                return Err(self.error(
+                    format!(
+                        "Expected ',' or ')' after an argument, found {}.",
+                        self.found()
+                    ),
+                    self.current,
+                ));
+            }
+        }
+
+        if args.len() != params.len() {
+            return Err(self.error(
+                format!(
+                    "{}() takes {} but {} given.",
+                    name,
+                    arguments(params.len()),
+                    match args.len() {
+                        1 => "1 was".to_string(),
+                        n => format!("{} were", n),
+                    }
+                ),
+                start,
+            ));
+        }
+
+        // literal patterns are compiled once here instead of for every node
+        let regex = match (function, &args[..]) {
+            (
+                Function::Match | Function::Search,
+                [_, Argument::Value(Comparable::Literal(Value::String(pattern)))],
+            ) => Some(eval::regex(pattern, function == Function::Match)),
+            _ => None,
+        };
+
+        Ok(FunctionCall {
+            function,
+            args,
+            regex,
+        })
+    }
+
+    /// An argument of `function` checked against the type of its parameter.
+    fn argument(&mut self, function: Function, param: Type) -> Result<Argument, PathError> {
+        let start = self.current;
+
+        let expression = self.expression()?;
+
+        match (param, expression) {
+            (Type::Value, Expression::Operand(operand)) => {
+                Ok(Argument::Value(self.comparable(operand, start)?))
+            }
+            (Type::Value, Expression::Logical(_)) => Err(self.error(
+                format!(
+                    "{}() takes a value here, not a logical expression.",
+                    function.name()
+                ),
+                start,
+            )),
+            (Type::Nodes, Expression::Operand(Comparable::Query(query))) => {
+                Ok(Argument::Nodes(query))
+            }
+            (Type::Nodes, _) => {
+                Err(self.error(format!("{}() takes a query here.", function.name()), start))
+            }
+            (Type::Logical, Expression::Operand(operand)) => {
+                Ok(Argument::Logical(self.test(operand, start)?))
+            }
+            (Type::Logical, Expression::Logical(logical)) => Ok(Argument::Logical(logical)),
+        }
+    }
+
+    /// A single operand unless it is the start of a larger logical expression.
+    fn expression(&mut self) -> Result<Expression, PathError> {
+        let start = self.current;
+
+        if !matches!(self.peek(), '!' | '(') {
+            let operand = self.operand()?;
+            let end = self.current;
+
+            self.skip_blank();
+
+            let larger = self.comparison_op().is_some() || self.mat("&&") || self.mat("||");
+
+            self.current = end;
+
+            if !larger {
+                return Ok(Expression::Operand(operand));
+            }
+        }
+
+        self.current = start;
+
+        Ok(Expression::Logical(self.logical()?))
+    }
+
+    /// Checks that an operand of a comparison or value argument is a single value.
+    fn comparable(&self, operand: Comparable, start: usize) -> Result<Comparable, PathError> {
+        match operand {
+            Comparable::Query(query) if !query.is_singular() => Err(self.error(
+                "A query with '..', wildcards, slices or filters can select more than one \
+                 value, it can't be compared or used as a value.",
+                start,
+            )),
+            Comparable::Function(call) if call.function.result() != Type::Value => Err(self.error(
+                format!(
+                    "{}() is true or false, it can't be compared.",
+                    call.function.name()
+                ),
+                start,
+            )),
+            operand => Ok(operand),
+        }
+    }
+
+    /// Checks that an operand on its own is something that is true or false.
+    fn test(&self, operand: Comparable, start: usize) -> Result<Logical, PathError> {
+        match operand {
+            Comparable::Query(query) => Ok(Logical::Exists(query)),
+            Comparable::Function(call) if call.function.result() == Type::Value => Err(self.error(
+                format!(
+                    "{}() returns a value, compare it to use it as a test.",
+                    call.function.name()
+                ),
+                start,
+            )),
+            Comparable::Function(call) => Ok(Logical::Function(call)),
+            Comparable::Literal(_) => Err(self.error(
+                "A literal isn't a test on its own, compare it to something.",
+                start,
+            )),
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), ' ' | '\t' | '\n' | '\r') {
+            self.advance();
+        }
+    }
+
+    /// Consumes `lexeme` if the source continues with it.
+    fn mat(&mut self, lexeme: &str) -> bool {
+        if self.source[self.current..].starts_with(lexeme) {
+            self.current += lexeme.len();
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn advance(&mut self) -> char {
+        let c = self.peek();
+
+        if !self.is_at_end() {
+            self.current += c.len_utf8();
+        }
+
+        c
+    }
+
+    /// The current char or `'\0'` at the end.
+    fn peek(&self) -> char {
+        self.source[self.current..].chars().next().unwrap_or('\0')
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+
+    /// Describes the current char for error messages.
+    fn found(&self) -> String {
+        if self.is_at_end() {
+            "the end of the query".to_string()
+        } else {
+            format!("'{}'", self.peek().escape_debug())
+        }
+    }
+
+    fn error(&self, message: impl Into<String>, offset: usize) -> PathError {
+        let mut line = 1;
+        let mut column = 1;
+
+        for c in self.source[..offset].chars() {
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        PathError {
+            message: message.into(),
+            position: Position::new(offset, line, column),
+        }
+    }
+}
+
+fn is_name_first(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c >= '\u{80}'
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_first(c) || c.is_ascii_digit()
+}
+
+fn arguments(n: usize) -> String {
+    match n {
+        1 => "1 argument".to_string(),
+        n => format!("{} arguments", n),
+    }
+}
@@ -0,0 +1,297 @@
+use std::{borrow::Cow, fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::parser::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PointerError {
+    /// The pointer isn't empty and doesn't start with `/`
+    #[error("Pointer Error: '{0}' must be empty or start with '/'.")]
+    Syntax(String),
+    /// A `~` in a segment isn't followed by `0` or `1`
+    #[error("Pointer Error: '{0}' has a '~' that isn't followed by '0' or '1'.")]
+    Escape(String),
+    /// The object doesn't have the key
+    #[error("Pointer Error: {at}: No property '{key}' in the object.")]
+    MissingKey { at: String, key: String },
+    /// The position is past the end of the array, `-` is always past the end
+    #[error("Pointer Error: {at}: Index {index} is out of bounds for an array of length {len}.")]
+    OutOfBounds {
+        at: String,
+        index: usize,
+        len: usize,
+    },
+    /// The segment isn't `0` or a number without leading zeros
+    #[error("Pointer Error: {at}: '{token}' isn't an array index.")]
+    InvalidIndex { at: String, token: String },
+    /// The segment steps into something that isn't an object or array
+    #[error("Pointer Error: {at}: Can't look up '{token}' in a {kind}.")]
+    NotContainer {
+        at: String,
+        token: String,
+        kind: &'static str,
+    },
+    /// The empty pointer was removed
+    #[error("Pointer Error: The root value can't be removed.")]
+    RemoveRoot,
+}
+
+/// An RFC 6901 pointer like `/servers/0/host`, a list of unescaped keys or positions
+/// from the top level value. `~1` is read as `/` and `~0` as `~`.
+///
+/// The errors of every lookup have `at`, the pointer up to the segment that failed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct JsonPointer {
+    tokens: Vec<String>,
+}
+
+impl JsonPointer {
+    /// The empty pointer, which is the top level value.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(pointer: &str) -> Result<Self, PointerError> {
+        if pointer.is_empty() {
+            return Ok(Self::root());
+        }
+
+        let Some(rest) = pointer.strip_prefix('/') else {
+            return Err(PointerError::Syntax(pointer.to_string()));
+        };
+
+        let tokens = rest
+            .split('/')
+            .map(unescape)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { tokens })
+    }
+
+    /// The unescaped segments.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Adds an unescaped segment to the end.
+    pub fn push(&mut self, token: impl Into<String>) {
+        self.tokens.push(token.into());
+    }
+
+    /// The pointer to the value containing this one, `None` for the root.
+    pub fn parent(&self) -> Option<JsonPointer> {
+        let (_, parent) = self.tokens.split_last()?;
+
+        Some(Self {
+            tokens: parent.to_vec(),
+        })
+    }
+
+    pub fn get<'v, 'a>(&self, value: &'v Value<'a>) -> Result<&'v Value<'a>, PointerError> {
+        let mut current = value;
+
+        for (i, token) in self.tokens.iter().enumerate() {
+            current = match current {
+                Value::Object(properties) => {
+                    properties
+                        .get(token.as_str())
+                        .ok_or_else(|| PointerError::MissingKey {
+                            at: self.at(i),
+                            key: token.clone(),
+                        })?
+                }
+                Value::Array(values) => &values[self.index(i, values.len(), false)?],
+                other => return Err(self.not_container(i, other)),
+            };
+        }
+
+        Ok(current)
+    }
+
+    pub fn get_mut<'v, 'a>(
+        &self,
+        value: &'v mut Value<'a>,
+    ) -> Result<&'v mut Value<'a>, PointerError> {
+        let mut current = value;
+
+        for (i, token) in self.tokens.iter().enumerate() {
+            current = match current {
+                Value::Object(properties) => {
+                    properties
+                        .get_mut(token.as_str())
+                        .ok_or_else(|| PointerError::MissingKey {
+                            at: self.at(i),
+                            key: token.clone(),
+                        })?
+                }
+                Value::Array(values) => {
+                    let index = self.index(i, values.len(), false)?;
+
+                    &mut values[index]
+                }
+                other => return Err(self.not_container(i, other)),
+            };
+        }
+
+        Ok(current)
+    }
+
+    /// See [`Value::insert_at`].
+    pub fn insert<'a>(
+        &self,
+        value: &mut Value<'a>,
+        new: Value<'a>,
+    ) -> Result<Option<Value<'a>>, PointerError> {
+        let Some((token, parent)) = self.split_last(value)? else {
+            return Ok(Some(std::mem::replace(value, new)));
+        };
+
+        let last = self.tokens.len() - 1;
+
+        match parent {
+            Value::Object(properties) => Ok(properties.insert(Cow::Owned(token.to_string()), new)),
+            Value::Array(values) => {
+                let index = self.index(last, values.len(), true)?;
+
+                values.insert(index, new);
+
+                Ok(None)
+            }
+            other => Err(self.not_container(last, other)),
+        }
+    }
+
+    /// See [`Value::remove_at`].
+    pub fn remove<'a>(&self, value: &mut Value<'a>) -> Result<Value<'a>, PointerError> {
+        let Some((token, parent)) = self.split_last(value)? else {
+            return Err(PointerError::RemoveRoot);
+        };
+
+        let last = self.tokens.len() - 1;
+
+        match parent {
+            Value::Object(properties) => {
+                properties
+                    .remove(token)
+                    .ok_or_else(|| PointerError::MissingKey {
+                        at: self.at(last),
+                        key: token.to_string(),
+                    })
+            }
+            Value::Array(values) => {
+                let index = self.index(last, values.len(), false)?;
+
+                Ok(values.remove(index))
+            }
+            other => Err(self.not_container(last, other)),
+        }
+    }
+
+    /// Looks up the value containing the last segment, `None` for the root.
+    fn split_last<'s, 'v, 'a>(
+        &'s self,
+        value: &'v mut Value<'a>,
+    ) -> Result<Option<(&'s str, &'v mut Value<'a>)>, PointerError> {
+        let Some((token, parent)) = self.tokens.split_last() else {
+            return Ok(None);
+        };
+
+        // errors of the parent are the same as looking it up through this pointer
+        let parent = Self {
+            tokens: parent.to_vec(),
+        }
+        .get_mut(value)?;
+
+        Ok(Some((token, parent)))
+    }
+
+    /// Reads segment `i` as a position in an array of `len` elements, `end` allows the
+    /// position right after the last element.
+    fn index(&self, i: usize, len: usize, end: bool) -> Result<usize, PointerError> {
+        let token = &self.tokens[i];
+
+        let index = if token == "-" {
+            len
+        } else if token == "0"
+            || (!token.starts_with('0') && token.bytes().all(|b| b.is_ascii_digit()))
+        {
+            token.parse().map_err(|_| PointerError::InvalidIndex {
+                at: self.at(i),
+                token: token.clone(),
+            })?
+        } else {
+            return Err(PointerError::InvalidIndex {
+                at: self.at(i),
+                token: token.clone(),
+            });
+        };
+
+        if index < len || (end && index == len) {
+            Ok(index)
+        } else {
+            Err(PointerError::OutOfBounds {
+                at: self.at(i),
+                index,
+                len,
+            })
+        }
+    }
+
+    fn not_container(&self, i: usize, value: &Value) -> PointerError {
+        PointerError::NotContainer {
+            at: self.at(i),
+            token: self.tokens[i].clone(),
+            kind: value.kind(),
+        }
+    }
+
+    /// The escaped pointer up to and including segment `i`.
+    fn at(&self, i: usize) -> String {
+        Self {
+            tokens: self.tokens[..=i].to_vec(),
+        }
+        .to_string()
+    }
+}
+
+fn unescape(token: &str) -> Result<String, PointerError> {
+    let mut result = String::with_capacity(token.len());
+    let mut chars = token.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => result.push('~'),
+                Some('1') => result.push('/'),
+                _ => return Err(PointerError::Escape(token.to_string())),
+            },
+            c => result.push(c),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Writes the pointer escaping `~` and `/` in the segments.
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(pointer: &str) -> Result<Self, Self::Err> {
+        Self::parse(pointer)
+    }
+}
@@ -0,0 +1,996 @@
+use std::{borrow::Cow, fmt, io::Write};
+
+use serde::{
+    ser::{
+        self, Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant,
+        SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Serialize,
+};
+use thiserror::Error;
+
+use crate::{
+    parser::{map::Map, number::Number, Value},
+    serializer::SerializerOptions,
+    writer::{JsonWriter, WriterError},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    /// A float is `NaN` or infinite and the dialect doesn't allow it
+    #[error("Serialize Error: {} can't be represented in JSON, use a dialect with `non_finite` to write it.", non_finite_name(*.0))]
+    NonFinite(f64),
+    /// A map key can't be written as a string
+    #[error(
+        "Serialize Error: Map keys must be strings, numbers, booleans or unit variants, found {0}."
+    )]
+    KeyMustBeString(&'static str),
+    /// Error from the `Serialize` implementation
+    #[error("Serialize Error: {0}")]
+    Custom(String),
+    /// Error writing the output
+    #[error(transparent)]
+    Writer(#[from] WriterError),
+}
+
+/// The name JSON5 uses for the float.
+fn non_finite_name(f: f64) -> &'static str {
+    if f.is_nan() {
+        "NaN"
+    } else if f.is_sign_positive() {
+        "Infinity"
+    } else {
+        "-Infinity"
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self::Custom(msg.to_string())
+    }
+}
+
+/// Converts `value` into a [`Value`]. Floats that aren't finite are an error since a
+/// [`Value`] can't hold them.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value<'static>, Error> {
+    value.serialize(ValueSerializer)
+}
+
+/// Serializes `value` as compact JSON.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    to_string_with(value, &SerializerOptions::compact())
+}
+
+/// Serializes `value` as JSON indented by two spaces.
+pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    to_string_with(value, &SerializerOptions::pretty())
+}
+
+pub fn to_string_with<T: Serialize + ?Sized>(
+    value: &T,
+    options: &SerializerOptions,
+) -> Result<String, Error> {
+    let mut buf = Vec::new();
+
+    to_writer_with(&mut buf, value, options)?;
+
+    // only valid UTF-8 is ever written
+    Ok(String::from_utf8(buf).unwrap())
+}
+
+/// Writes `value` as compact JSON.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: &mut W, value: &T) -> Result<(), Error> {
+    to_writer_with(writer, value, &SerializerOptions::compact())
+}
+
+/// Writes `value` as JSON indented by two spaces.
+pub fn to_writer_pretty<W: Write, T: Serialize + ?Sized>(
+    writer: &mut W,
+    value: &T,
+) -> Result<(), Error> {
+    to_writer_with(writer, value, &SerializerOptions::pretty())
+}
+
+/// Writes `value` with `options`. Floats that aren't finite are an error unless the
+/// dialect allows them.
+pub fn to_writer_with<W: Write, T: Serialize + ?Sized>(
+    writer: &mut W,
+    value: &T,
+    options: &SerializerOptions,
+) -> Result<(), Error> {
+    let mut serializer = Serializer {
+        writer: JsonWriter::with_options(writer, options.clone()),
+        non_finite: options.dialect.non_finite,
+    };
+
+    value.serialize(&mut serializer)?;
+
+    serializer.writer.finish()?;
+
+    Ok(())
+}
+
+/// Writes JSON text through a [`JsonWriter`].
+///
+/// Enums are written the same way they are deserialized, unit variants as a string
+/// and every other variant as an object with a single property named after the variant.
+struct Serializer<W: Write> {
+    writer: JsonWriter<W>,
+    /// Copy of the dialect setting so floats can be checked before writing
+    non_finite: bool,
+}
+
+impl<'s, W: Write> ser::Serializer for &'s mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = Compound<'s, W>;
+    type SerializeTuple = Compound<'s, W>;
+    type SerializeTupleStruct = Compound<'s, W>;
+    type SerializeTupleVariant = Compound<'s, W>;
+    type SerializeMap = Compound<'s, W>;
+    type SerializeStruct = Compound<'s, W>;
+    type SerializeStructVariant = Compound<'s, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        Ok(self.writer.bool(v)?)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        Ok(self.writer.i64(v)?)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        Ok(self.writer.number(&Number::from(v))?)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        Ok(self.writer.u64(v)?)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        Ok(self.writer.number(&Number::from(v))?)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        if !v.is_finite() && !self.non_finite {
+            return Err(Error::NonFinite(v));
+        }
+
+        Ok(self.writer.f64(v)?)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        Ok(self.writer.string(v)?)
+    }
+
+    /// Bytes are written as an array of numbers
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.writer.begin_array()?;
+
+        for b in v {
+            self.writer.u64(*b as u64)?;
+        }
+
+        Ok(self.writer.end_array()?)
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(self.writer.null()?)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.writer.begin_object()?;
+        self.writer.key(variant)?;
+        value.serialize(&mut *self)?;
+
+        Ok(self.writer.end_object()?)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'s, W>, Error> {
+        self.writer.begin_array()?;
+
+        Ok(Compound {
+            ser: self,
+            variant: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'s, W>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'s, W>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'s, W>, Error> {
+        self.writer.begin_object()?;
+        self.writer.key(variant)?;
+        self.writer.begin_array()?;
+
+        Ok(Compound {
+            ser: self,
+            variant: true,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'s, W>, Error> {
+        self.writer.begin_object()?;
+
+        Ok(Compound {
+            ser: self,
+            variant: false,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'s, W>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'s, W>, Error> {
+        self.writer.begin_object()?;
+        self.writer.key(variant)?;
+        self.writer.begin_object()?;
+
+        Ok(Compound {
+            ser: self,
+            variant: true,
+        })
+    }
+}
+
+struct Compound<'s, W: Write> {
+    ser: &'s mut Serializer<W>,
+    /// The container is wrapped in an object for the enum variant that has to be closed too
+    variant: bool,
+}
+
+impl<W: Write> Compound<'_, W> {
+    fn end_variant(self) -> Result<(), Error> {
+        if self.variant {
+            self.ser.writer.end_object()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> SerializeSeq for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.ser.writer.end_array()?;
+        self.end_variant()
+    }
+}
+
+impl<W: Write> SerializeTuple for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl<W: Write> SerializeTupleStruct for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl<W: Write> SerializeTupleVariant for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl<W: Write> SerializeMap for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        let key = key.serialize(KeySerializer)?;
+
+        Ok(self.ser.writer.key(&key)?)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.ser.writer.end_object()?;
+        self.end_variant()
+    }
+}
+
+impl<W: Write> SerializeStruct for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.ser.writer.key(key)?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        SerializeMap::end(self)
+    }
+}
+
+impl<W: Write> SerializeStructVariant for Compound<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        SerializeMap::end(self)
+    }
+}
+
+/// Turns map keys into strings. Numbers and booleans are written as strings the same
+/// way the deserializer reads them back.
+struct KeySerializer;
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<String, Error> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<String, Error> {
+        if !v.is_finite() {
+            return Err(Error::NonFinite(v));
+        }
+
+        Ok(v.to_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, Error> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, Error> {
+        Err(Error::KeyMustBeString("bytes"))
+    }
+
+    fn serialize_none(self) -> Result<String, Error> {
+        Err(Error::KeyMustBeString("none"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, Error> {
+        Err(Error::KeyMustBeString("unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Error> {
+        Err(Error::KeyMustBeString("a unit struct"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String, Error> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, Error> {
+        Err(Error::KeyMustBeString("a newtype variant"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(Error::KeyMustBeString("a sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(Error::KeyMustBeString("a tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(Error::KeyMustBeString("a tuple struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(Error::KeyMustBeString("a tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(Error::KeyMustBeString("a map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(Error::KeyMustBeString("a struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(Error::KeyMustBeString("a struct variant"))
+    }
+}
+
+/// Builds a [`Value`], enums are represented the same way as they are written as text.
+struct ValueSerializer;
+
+/// Wraps the content of an enum variant in an object with a single property.
+fn variant_object(variant: &'static str, value: Value<'static>) -> Value<'static> {
+    let mut properties = Map::new();
+
+    properties.insert(Cow::Borrowed(variant), value);
+
+    Value::Object(properties)
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value<'static>;
+    type Error = Error;
+
+    type SerializeSeq = ArrayBuilder;
+    type SerializeTuple = ArrayBuilder;
+    type SerializeTupleStruct = ArrayBuilder;
+    type SerializeTupleVariant = ArrayBuilder;
+    type SerializeMap = ObjectBuilder;
+    type SerializeStruct = ObjectBuilder;
+    type SerializeStructVariant = ObjectBuilder;
+
+    fn serialize_bool(self, v: bool) -> Result<Value<'static>, Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value<'static>, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value<'static>, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value<'static>, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value<'static>, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Value<'static>, Error> {
+        Ok(Value::Number(Number::from(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value<'static>, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value<'static>, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value<'static>, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value<'static>, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Value<'static>, Error> {
+        Ok(Value::Number(Number::from(v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value<'static>, Error> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value<'static>, Error> {
+        match Number::from_f64(v) {
+            Some(num) => Ok(Value::Number(num)),
+            None => Err(Error::NonFinite(v)),
+        }
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value<'static>, Error> {
+        Ok(Value::String(Cow::Owned(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value<'static>, Error> {
+        Ok(Value::String(Cow::Owned(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value<'static>, Error> {
+        Ok(Value::Array(v.iter().map(|b| Value::from(*b)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value<'static>, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value<'static>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value<'static>, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value<'static>, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value<'static>, Error> {
+        Ok(Value::String(Cow::Borrowed(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value<'static>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value<'static>, Error> {
+        Ok(variant_object(variant, value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ArrayBuilder, Error> {
+        Ok(ArrayBuilder {
+            values: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArrayBuilder, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ArrayBuilder, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ArrayBuilder, Error> {
+        Ok(ArrayBuilder {
+            values: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<ObjectBuilder, Error> {
+        Ok(ObjectBuilder {
+            properties: Map::new(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<ObjectBuilder, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<ObjectBuilder, Error> {
+        Ok(ObjectBuilder {
+            properties: Map::new(),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+struct ArrayBuilder {
+    values: Vec<Value<'static>>,
+    /// The enum variant the array is wrapped in
+    variant: Option<&'static str>,
+}
+
+impl SerializeSeq for ArrayBuilder {
+    type Ok = Value<'static>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.values.push(value.serialize(ValueSerializer)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value<'static>, Error> {
+        let value = Value::Array(self.values);
+
+        Ok(match self.variant {
+            Some(variant) => variant_object(variant, value),
+            None => value,
+        })
+    }
+}
+
+impl SerializeTuple for ArrayBuilder {
+    type Ok = Value<'static>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<'static>, Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl SerializeTupleStruct for ArrayBuilder {
+    type Ok = Value<'static>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<'static>, Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+impl SerializeTupleVariant for ArrayBuilder {
+    type Ok = Value<'static>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value<'static>, Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+struct ObjectBuilder {
+    properties: Map<Cow<'static, str>, Value<'static>>,
+    /// The key waiting for its value
+    key: Option<String>,
+    /// The enum variant the object is wrapped in
+    variant: Option<&'static str>,
+}
+
+impl SerializeMap for ObjectBuilder {
+    type Ok = Value<'static>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.key = Some(key.serialize(KeySerializer)?);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let Some(key) = self.key.take() else {
+            return Err(Error::Writer(WriterError::ExpectedKey));
+        };
+
+        self.properties
+            .insert(Cow::Owned(key), value.serialize(ValueSerializer)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value<'static>, Error> {
+        let value = Value::Object(self.properties);
+
+        Ok(match self.variant {
+            Some(variant) => variant_object(variant, value),
+            None => value,
+        })
+    }
+}
+
+impl SerializeStruct for ObjectBuilder {
+    type Ok = Value<'static>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.properties
+            .insert(Cow::Borrowed(key), value.serialize(ValueSerializer)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value<'static>, Error> {
+        SerializeMap::end(self)
+    }
+}
+
+impl SerializeStructVariant for ObjectBuilder {
+    type Ok = Value<'static>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Value<'static>, Error> {
+        SerializeMap::end(self)
+    }
+}
+
+impl Serialize for Value<'_> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(num) => num.serialize(serializer),
+            Value::String(str) => serializer.serialize_str(str),
+            Value::Array(values) => serializer.collect_seq(values),
+            Value::Object(properties) => serializer.collect_map(properties),
+        }
+    }
+}
+
+impl Serialize for Number {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(n) = self.as_u64() {
+            return serializer.serialize_u64(n);
+        }
+
+        if let Some(n) = self.as_i64() {
+            return serializer.serialize_i64(n);
+        }
+
+        // integers kept by `arbitrary_precision` that need more than 64 bits
+        if self.is_decimal() {
+            if let Ok(n) = self.to_u128() {
+                return serializer.serialize_u128(n);
+            }
+
+            if let Ok(n) = self.to_i128() {
+                return serializer.serialize_i128(n);
+            }
+        }
+
+        serializer.serialize_f64(self.as_f64().unwrap_or(f64::NAN))
+    }
+}
@@ -0,0 +1,510 @@
+use std::io::{self, Write};
+
+use crate::parser::{
+    number::Number,
+    tokens::keywords,
+    utils::{is_identifier_part, is_identifier_start},
+    Value,
+};
+
+/// The character strings are wrapped in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quote {
+    #[default]
+    Double,
+    /// Only readable with [`crate::parser::Options::allow_single_quotes`]
+    Single,
+}
+
+impl Quote {
+    fn char(&self) -> char {
+        match self {
+            Quote::Double => '"',
+            Quote::Single => '\'',
+        }
+    }
+}
+
+/// The flavour of JSON that is written, the output side of [`crate::parser::Options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dialect {
+    /// Leaves keys unquoted when they are valid identifiers
+    ///
+    /// # Example
+    /// ```json
+    /// {
+    ///   foo: "bar",
+    ///   "not an identifier": 1
+    /// }
+    /// ```
+    pub unquoted_keys: bool,
+    /// The quotes used for strings and quoted keys
+    pub quote: Quote,
+    /// Adds a comma after the last element or property of a container spanning multiple
+    /// lines. Only has an effect on pretty output.
+    ///
+    /// # Example
+    /// ```json
+    /// [
+    ///   1,
+    ///   2,
+    /// ]
+    /// ```
+    pub trailing_comma: bool,
+    /// Writes floats that aren't finite as `NaN`, `Infinity` and `-Infinity` as JSON5 allows,
+    /// otherwise they are written as `null`
+    pub non_finite: bool,
+    /// Writes integers in hexadecimal such as `0xff` as JSON5 allows
+    pub hex_integers: bool,
+}
+
+impl Dialect {
+    /// Plain JSON, the same as [`Dialect::default`]
+    pub fn json() -> Self {
+        Self::default()
+    }
+
+    /// JavaScript style output that can be read back with [`crate::parser::Options::js`]
+    ///
+    /// ```text
+    /// {
+    ///   unquoted_keys: true,
+    ///   quote: Quote::Single,
+    ///   trailing_comma: true,
+    ///   non_finite: false,
+    ///   hex_integers: false,
+    /// }
+    /// ```
+    pub fn js() -> Self {
+        Self {
+            unquoted_keys: true,
+            quote: Quote::Single,
+            trailing_comma: true,
+            non_finite: false,
+            hex_integers: false,
+        }
+    }
+
+    /// Everything [JSON5](https://json5.org) allows. Note that the parser doesn't read
+    /// `NaN`, `Infinity` or hexadecimal numbers.
+    ///
+    /// ```text
+    /// {
+    ///   unquoted_keys: true,
+    ///   quote: Quote::Single,
+    ///   trailing_comma: true,
+    ///   non_finite: true,
+    ///   hex_integers: true,
+    /// }
+    /// ```
+    pub fn json5() -> Self {
+        Self {
+            non_finite: true,
+            hex_integers: true,
+            ..Self::js()
+        }
+    }
+}
+
+impl Default for Dialect {
+    /// Plain JSON
+    ///
+    /// ```text
+    /// {
+    ///   unquoted_keys: false,
+    ///   quote: Quote::Double,
+    ///   trailing_comma: false,
+    ///   non_finite: false,
+    ///   hex_integers: false,
+    /// }
+    /// ```
+    fn default() -> Self {
+        Self {
+            unquoted_keys: false,
+            quote: Quote::Double,
+            trailing_comma: false,
+            non_finite: false,
+            hex_integers: false,
+        }
+    }
+}
+
+/// Options for how a [`Value`] is written.
+#[derive(Debug, Clone)]
+pub struct SerializerOptions {
+    /// Puts every element and property on its own line indented by `indent`
+    ///
+    /// # Example
+    /// ```json
+    /// {
+    ///   "foo": [
+    ///     1,
+    ///     2
+    ///   ]
+    /// }
+    /// ```
+    pub pretty: bool,
+    /// What is written once per level of nesting when `pretty` is set
+    pub indent: String,
+    /// Writes the properties of objects sorted by key instead of in the object's order
+    pub sort_keys: bool,
+    /// Ends the output with a line feed
+    pub final_newline: bool,
+    /// Escapes everything outside of ASCII as `\uXXXX` (using surrogate pairs where needed)
+    /// so the output survives transports that aren't UTF-8 clean
+    pub ascii_only: bool,
+    /// Escapes `<`, `>` and `&` as `\u003c`, `\u003e` and `\u0026` so the output can be
+    /// embedded in HTML such as a `<script>` tag
+    pub escape_html: bool,
+    /// The flavour of JSON to write, use [`Dialect::js`] to write what [`crate::parser::Options::js`] reads
+    pub dialect: Dialect,
+}
+
+impl SerializerOptions {
+    /// Human readable output indented by two spaces
+    ///
+    /// ```text
+    /// {
+    ///   pretty: true,
+    ///   indent: "  ",
+    ///   sort_keys: false,
+    ///   final_newline: false,
+    ///   ascii_only: false,
+    ///   escape_html: false,
+    ///   dialect: Dialect::json(),
+    /// }
+    /// ```
+    pub fn pretty() -> Self {
+        Self {
+            pretty: true,
+            ..Self::default()
+        }
+    }
+
+    /// The smallest possible output, the same as [`SerializerOptions::default`]
+    pub fn compact() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for SerializerOptions {
+    /// Compact output
+    ///
+    /// ```text
+    /// {
+    ///   pretty: false,
+    ///   indent: "  ",
+    ///   sort_keys: false,
+    ///   final_newline: false,
+    ///   ascii_only: false,
+    ///   escape_html: false,
+    ///   dialect: Dialect::json(),
+    /// }
+    /// ```
+    fn default() -> Self {
+        Self {
+            pretty: false,
+            indent: "  ".to_string(),
+            sort_keys: false,
+            final_newline: false,
+            ascii_only: false,
+            escape_html: false,
+            dialect: Dialect::json(),
+        }
+    }
+}
+
+/// Serializes `value` into a compact JSON string.
+pub fn to_string(value: &Value) -> String {
+    to_string_with(value, &SerializerOptions::compact())
+}
+
+/// Serializes `value` into a JSON string indented by two spaces.
+pub fn to_string_pretty(value: &Value) -> String {
+    to_string_with(value, &SerializerOptions::pretty())
+}
+
+/// Serializes `value` into a JSON string according to `options`.
+pub fn to_string_with(value: &Value, options: &SerializerOptions) -> String {
+    let mut buf = Vec::new();
+
+    // writing into a `Vec` can't fail
+    to_writer_with(&mut buf, value, options).unwrap();
+
+    // only valid UTF-8 is ever written
+    String::from_utf8(buf).unwrap()
+}
+
+/// Serializes `value` as compact JSON into `writer`.
+pub fn to_writer<W: Write>(writer: &mut W, value: &Value) -> io::Result<()> {
+    to_writer_with(writer, value, &SerializerOptions::compact())
+}
+
+/// Serializes `value` as JSON indented by two spaces into `writer`.
+pub fn to_writer_pretty<W: Write>(writer: &mut W, value: &Value) -> io::Result<()> {
+    to_writer_with(writer, value, &SerializerOptions::pretty())
+}
+
+/// Serializes `value` into `writer` according to `options`.
+pub fn to_writer_with<W: Write>(
+    writer: &mut W,
+    value: &Value,
+    options: &SerializerOptions,
+) -> io::Result<()> {
+    let mut serializer = Serializer {
+        writer,
+        options,
+        depth: 0,
+    };
+
+    serializer.value(value)?;
+
+    if options.final_newline {
+        serializer.writer.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+pub(crate) struct Serializer<'w, 'o, W> {
+    pub(crate) writer: &'w mut W,
+    pub(crate) options: &'o SerializerOptions,
+    /// How many containers are open
+    pub(crate) depth: usize,
+}
+
+impl<W: Write> Serializer<'_, '_, W> {
+    pub(crate) fn value(&mut self, value: &Value) -> io::Result<()> {
+        match value {
+            Value::Object(properties) => {
+                if properties.is_empty() {
+                    return self.writer.write_all(b"{}");
+                }
+
+                let mut entries: Vec<_> = properties.iter().collect();
+
+                // stable so keys that are there more than once keep their order
+                if self.options.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                }
+
+                self.writer.write_all(b"{")?;
+                self.depth += 1;
+
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        self.writer.write_all(b",")?;
+                    }
+
+                    self.new_line()?;
+                    write_key(self.writer, key, self.options)?;
+                    write_colon(self.writer, self.options)?;
+                    self.value(value)?;
+                }
+
+                write_trailing_comma(self.writer, self.options)?;
+
+                self.depth -= 1;
+                self.new_line()?;
+
+                self.writer.write_all(b"}")
+            }
+            Value::Array(values) => {
+                if values.is_empty() {
+                    return self.writer.write_all(b"[]");
+                }
+
+                self.writer.write_all(b"[")?;
+                self.depth += 1;
+
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        self.writer.write_all(b",")?;
+                    }
+
+                    self.new_line()?;
+                    self.value(value)?;
+                }
+
+                write_trailing_comma(self.writer, self.options)?;
+
+                self.depth -= 1;
+                self.new_line()?;
+
+                self.writer.write_all(b"]")
+            }
+            Value::String(str) => write_string(self.writer, str, self.options),
+            Value::Number(num) => write_number(self.writer, num, self.options),
+            Value::Bool(true) => self.writer.write_all(b"true"),
+            Value::Bool(false) => self.writer.write_all(b"false"),
+            Value::Null => self.writer.write_all(b"null"),
+        }
+    }
+
+    fn new_line(&mut self) -> io::Result<()> {
+        write_indent(self.writer, self.depth, self.options)
+    }
+}
+
+/// Writes a line feed followed by `depth` indents when writing pretty output.
+pub(crate) fn write_indent<W: Write>(
+    writer: &mut W,
+    depth: usize,
+    options: &SerializerOptions,
+) -> io::Result<()> {
+    if !options.pretty {
+        return Ok(());
+    }
+
+    writer.write_all(b"\n")?;
+
+    for _ in 0..depth {
+        writer.write_all(options.indent.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Writes the colon between a key and its value, followed by a space when writing pretty output.
+pub(crate) fn write_colon<W: Write>(writer: &mut W, options: &SerializerOptions) -> io::Result<()> {
+    if options.pretty {
+        writer.write_all(b": ")
+    } else {
+        writer.write_all(b":")
+    }
+}
+
+/// Writes the comma after the last element of a container when the dialect asks for it.
+pub(crate) fn write_trailing_comma<W: Write>(
+    writer: &mut W,
+    options: &SerializerOptions,
+) -> io::Result<()> {
+    if options.pretty && options.dialect.trailing_comma {
+        writer.write_all(b",")?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_number<W: Write>(
+    writer: &mut W,
+    num: &Number,
+    options: &SerializerOptions,
+) -> io::Result<()> {
+    // floats and decimals are never written in hexadecimal
+    if options.dialect.hex_integers && !num.is_f64() && !num.is_decimal() {
+        if let Some(n) = num.as_u64() {
+            return write!(writer, "0x{:x}", n);
+        }
+
+        if let Some(n) = num.as_i64() {
+            return write!(writer, "-0x{:x}", n.unsigned_abs());
+        }
+    }
+
+    match num.as_f64() {
+        // floats that weren't parsed have no lexeme to write
+        Some(f) if num.is_f64() && num.as_str().is_none() => write_f64(writer, f, options),
+        _ => write!(writer, "{}", num),
+    }
+}
+
+/// Writes a float, floats that aren't finite are written as `null` unless the
+/// dialect allows them.
+pub(crate) fn write_f64<W: Write>(
+    writer: &mut W,
+    f: f64,
+    options: &SerializerOptions,
+) -> io::Result<()> {
+    if f.is_finite() {
+        // debug formatting keeps the `.0` and switches to exponents for large values
+        return write!(writer, "{:?}", f);
+    }
+
+    if !options.dialect.non_finite {
+        return writer.write_all(b"null");
+    }
+
+    if f.is_nan() {
+        writer.write_all(b"NaN")
+    } else if f.is_sign_positive() {
+        writer.write_all(b"Infinity")
+    } else {
+        writer.write_all(b"-Infinity")
+    }
+}
+
+/// Writes a key leaving it unquoted when the dialect allows it and it is a valid identifier.
+pub(crate) fn write_key<W: Write>(
+    writer: &mut W,
+    key: &str,
+    options: &SerializerOptions,
+) -> io::Result<()> {
+    if options.dialect.unquoted_keys && is_unquotable(key, options) {
+        return writer.write_all(key.as_bytes());
+    }
+
+    write_string(writer, key, options)
+}
+
+/// Can `key` be written without quotes and still be read back as the same key.
+fn is_unquotable(key: &str, options: &SerializerOptions) -> bool {
+    // keywords would be read as literals
+    if keywords().contains_key(key) || (options.ascii_only && !key.is_ascii()) {
+        return false;
+    }
+
+    let mut chars = key.chars();
+
+    chars.next().is_some_and(|c| is_identifier_start(&c)) && chars.all(|c| is_identifier_part(&c))
+}
+
+/// Writes `str` as a quoted JSON string escaping what has to be escaped
+/// along with what `options` asks for.
+pub(crate) fn write_string<W: Write>(
+    writer: &mut W,
+    str: &str,
+    options: &SerializerOptions,
+) -> io::Result<()> {
+    let quote = options.dialect.quote.char();
+
+    write!(writer, "{}", quote)?;
+
+    let mut start = 0;
+
+    for (i, c) in str.char_indices() {
+        let escape = match c {
+            '"' if quote == '"' => "\\\"",
+            '\'' if quote == '\'' => "\\'",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{8}' => "\\b",
+            '\u{c}' => "\\f",
+            '\u{0}'..='\u{1f}' => "",
+            '<' | '>' | '&' if options.escape_html => "",
+            _ if options.ascii_only && !c.is_ascii() => "",
+            _ => continue,
+        };
+
+        writer.write_all(&str.as_bytes()[start..i])?;
+
+        if escape.is_empty() {
+            // characters outside of the BMP are written as a surrogate pair
+            let mut units = [0; 2];
+
+            for unit in c.encode_utf16(&mut units) {
+                write!(writer, "\\u{:04x}", unit)?;
+            }
+        } else {
+            writer.write_all(escape.as_bytes())?;
+        }
+
+        start = i + c.len_utf8();
+    }
+
+    writer.write_all(&str.as_bytes()[start..])?;
+
+    write!(writer, "{}", quote)
+}
@@ -0,0 +1,327 @@
+use std::io::{self, Write};
+
+use thiserror::Error;
+
+use crate::{
+    parser::{number::Number, Value},
+    serializer::{
+        write_colon, write_f64, write_indent, write_key, write_number, write_string,
+        write_trailing_comma, Serializer, SerializerOptions,
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum WriterError {
+    /// A value or container was written in an object where a key was expected
+    #[error("Writer Error: Expected a key before the value.")]
+    ExpectedKey,
+    /// An object was ended or the writer finished while a key was missing its value
+    #[error("Writer Error: Expected a value for the key.")]
+    ExpectedValue,
+    /// A key was written outside of an object
+    #[error("Writer Error: A key can only be written in an object.")]
+    UnexpectedKey,
+    /// An object or array was ended that isn't the innermost open container
+    #[error("Writer Error: '{0}' doesn't close the open container.")]
+    Mismatched(char),
+    /// The writer finished while containers were still open
+    #[error("Writer Error: {0} container(s) were never closed.")]
+    Unclosed(usize),
+    /// The writer finished without a value being written
+    #[error("Writer Error: No value was written.")]
+    Empty,
+    /// Something was written after the top level value was complete
+    #[error("Writer Error: The top level value is already complete.")]
+    Complete,
+    /// Error occurs writing to the underlying stream
+    #[error("IO Error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug)]
+enum Container {
+    Object {
+        len: usize,
+        /// A key has been written that is still waiting for its value
+        has_key: bool,
+    },
+    Array {
+        len: usize,
+    },
+}
+
+/// Writes JSON a piece at a time without building a [`Value`] first.
+///
+/// The writer keeps the stack of open containers so that the output is always valid
+/// JSON, anything written out of place fails with a [`WriterError`].
+///
+/// ```text
+/// let mut writer = JsonWriter::pretty(std::io::stdout());
+///
+/// writer.begin_object()?;
+/// writer.key("name")?;
+/// writer.string("ada")?;
+/// writer.key("tags")?;
+/// writer.begin_array()?;
+/// writer.string("admin")?;
+/// writer.end_array()?;
+/// writer.end_object()?;
+///
+/// writer.finish()?;
+/// ```
+pub struct JsonWriter<W: Write> {
+    writer: W,
+    options: SerializerOptions,
+    stack: Vec<Container>,
+    /// The top level value has been written
+    complete: bool,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// Creates a writer that writes compact JSON.
+    pub fn new(writer: W) -> Self {
+        Self::with_options(writer, SerializerOptions::compact())
+    }
+
+    /// Creates a writer that writes pretty JSON.
+    pub fn pretty(writer: W) -> Self {
+        Self::with_options(writer, SerializerOptions::pretty())
+    }
+
+    /// Creates a writer with the same options as [`crate::serializer::to_writer_with`].
+    /// `sort_keys` only applies to objects written with [`JsonWriter::value`].
+    pub fn with_options(writer: W, options: SerializerOptions) -> Self {
+        Self {
+            writer,
+            options,
+            stack: Vec::new(),
+            complete: false,
+        }
+    }
+
+    /// How many containers are open.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriterError> {
+        self.begin_value()?;
+
+        self.writer.write_all(b"{")?;
+        self.stack.push(Container::Object {
+            len: 0,
+            has_key: false,
+        });
+
+        Ok(())
+    }
+
+    pub fn end_object(&mut self) -> Result<(), WriterError> {
+        let len = match self.stack.last() {
+            Some(Container::Object { has_key: true, .. }) => {
+                return Err(WriterError::ExpectedValue)
+            }
+            Some(Container::Object { len, .. }) => *len,
+            _ => return Err(WriterError::Mismatched('}')),
+        };
+
+        self.end(len, b"}")
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriterError> {
+        self.begin_value()?;
+
+        self.writer.write_all(b"[")?;
+        self.stack.push(Container::Array { len: 0 });
+
+        Ok(())
+    }
+
+    pub fn end_array(&mut self) -> Result<(), WriterError> {
+        let len = match self.stack.last() {
+            Some(Container::Array { len }) => *len,
+            _ => return Err(WriterError::Mismatched(']')),
+        };
+
+        self.end(len, b"]")
+    }
+
+    /// Writes the key of the next property of the open object.
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError> {
+        let depth = self.stack.len();
+
+        let Some(Container::Object { len, has_key }) = self.stack.last_mut() else {
+            return Err(WriterError::UnexpectedKey);
+        };
+
+        if *has_key {
+            return Err(WriterError::ExpectedValue);
+        }
+
+        if *len > 0 {
+            self.writer.write_all(b",")?;
+        }
+
+        write_indent(&mut self.writer, depth, &self.options)?;
+        write_key(&mut self.writer, key, &self.options)?;
+        write_colon(&mut self.writer, &self.options)?;
+
+        *len += 1;
+        *has_key = true;
+
+        Ok(())
+    }
+
+    /// Writes a whole property, the same as [`JsonWriter::key`] followed by [`JsonWriter::value`].
+    pub fn property(&mut self, key: &str, value: &Value) -> Result<(), WriterError> {
+        self.key(key)?;
+        self.value(value)
+    }
+
+    /// Writes a whole value, nested containers are indented to the current depth.
+    pub fn value(&mut self, value: &Value) -> Result<(), WriterError> {
+        self.begin_value()?;
+
+        let mut serializer = Serializer {
+            writer: &mut self.writer,
+            options: &self.options,
+            depth: self.stack.len(),
+        };
+
+        serializer.value(value)?;
+
+        self.end_value();
+
+        Ok(())
+    }
+
+    pub fn string(&mut self, str: &str) -> Result<(), WriterError> {
+        self.begin_value()?;
+        write_string(&mut self.writer, str, &self.options)?;
+        self.end_value();
+
+        Ok(())
+    }
+
+    pub fn number(&mut self, num: &Number) -> Result<(), WriterError> {
+        self.begin_value()?;
+        write_number(&mut self.writer, num, &self.options)?;
+        self.end_value();
+
+        Ok(())
+    }
+
+    pub fn i64(&mut self, n: i64) -> Result<(), WriterError> {
+        self.number(&Number::from(n))
+    }
+
+    pub fn u64(&mut self, n: u64) -> Result<(), WriterError> {
+        self.number(&Number::from(n))
+    }
+
+    /// Writes a float, floats that aren't finite are written as `null` unless the
+    /// dialect allows them.
+    pub fn f64(&mut self, f: f64) -> Result<(), WriterError> {
+        self.begin_value()?;
+        write_f64(&mut self.writer, f, &self.options)?;
+        self.end_value();
+
+        Ok(())
+    }
+
+    pub fn bool(&mut self, b: bool) -> Result<(), WriterError> {
+        self.begin_value()?;
+        self.writer.write_all(if b { b"true" } else { b"false" })?;
+        self.end_value();
+
+        Ok(())
+    }
+
+    pub fn null(&mut self) -> Result<(), WriterError> {
+        self.begin_value()?;
+        self.writer.write_all(b"null")?;
+        self.end_value();
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), WriterError> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Checks that a complete value was written, writes the final newline if it was
+    /// asked for and returns the inner writer.
+    pub fn finish(mut self) -> Result<W, WriterError> {
+        match self.stack.last() {
+            Some(Container::Object { has_key: true, .. }) => {
+                return Err(WriterError::ExpectedValue)
+            }
+            Some(_) => return Err(WriterError::Unclosed(self.stack.len())),
+            None if !self.complete => return Err(WriterError::Empty),
+            None => {}
+        }
+
+        if self.options.final_newline {
+            self.writer.write_all(b"\n")?;
+        }
+
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    /// Checks that a value can be written here and writes what goes before it.
+    fn begin_value(&mut self) -> Result<(), WriterError> {
+        let depth = self.stack.len();
+
+        match self.stack.last_mut() {
+            None if self.complete => Err(WriterError::Complete),
+            None => Ok(()),
+            Some(Container::Object { has_key, .. }) => {
+                if !*has_key {
+                    return Err(WriterError::ExpectedKey);
+                }
+
+                // the comma and indent were written with the key
+                *has_key = false;
+
+                Ok(())
+            }
+            Some(Container::Array { len }) => {
+                if *len > 0 {
+                    self.writer.write_all(b",")?;
+                }
+
+                *len += 1;
+
+                write_indent(&mut self.writer, depth, &self.options)?;
+
+                Ok(())
+            }
+        }
+    }
+
+    fn end_value(&mut self) {
+        if self.stack.is_empty() {
+            self.complete = true;
+        }
+    }
+
+    /// Closes the innermost container which has `len` elements.
+    fn end(&mut self, len: usize, close: &[u8]) -> Result<(), WriterError> {
+        self.stack.pop();
+
+        // empty containers are written as `{}` and `[]`
+        if len > 0 {
+            write_trailing_comma(&mut self.writer, &self.options)?;
+            write_indent(&mut self.writer, self.stack.len(), &self.options)?;
+        }
+
+        self.writer.write_all(close)?;
+        self.end_value();
+
+        Ok(())
+    }
+}